@@ -1,17 +1,35 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
 #[allow(dead_code)]
 pub struct WidgetWindow {
     pub width: u32,
     pub height: u32,
     pub name: String,
+    #[serde(deserialize_with = "deserialize_anchor")]
     pub location: iced_layershell::reexport::Anchor,
     pub exclusive: bool,
+    #[serde(deserialize_with = "deserialize_layer")]
     pub layer: iced_layershell::reexport::Layer,
 }
 
+impl Default for WidgetWindow {
+    fn default() -> Self {
+        Self {
+            width: 400,
+            height: 400,
+            name: String::new(),
+            location: iced_layershell::reexport::Anchor::Top
+                | iced_layershell::reexport::Anchor::Right,
+            exclusive: false,
+            layer: iced_layershell::reexport::Layer::Top,
+        }
+    }
+}
+
 //pub struct WidgetElement
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Global {
     pub antialiasing: bool,
     //pub output: String,
@@ -26,25 +44,39 @@ impl Default for Global {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
 #[allow(dead_code)]
 pub struct NotificationConfig {
     pub enable: bool,
+    #[serde(deserialize_with = "deserialize_anchor")]
     pub location: iced_layershell::reexport::Anchor,
-    pub local_expire_timeout: i32, //in seconds
+    pub local_expire_timeout: f64, //in seconds, 0 to never expire
     pub max_notifications: i32,    //0 for unlimited
     pub height: u32,
     pub width: u32,
     pub vertical_margin: i32,
     pub horizontal_margin: i32,
+    #[serde(deserialize_with = "deserialize_radius")]
     pub border_radius: iced::border::Radius,
+    #[serde(deserialize_with = "deserialize_color")]
     pub border_color: iced::Color,
     pub border_width: f32,
+    #[serde(deserialize_with = "deserialize_color")]
     pub primary_text_color: iced::Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub secondary_text_color: iced::Color,
+    #[serde(deserialize_with = "deserialize_color")]
     pub background_color: iced::Color,
-    pub respect_notification_icon: bool,
+    pub respect_notification_icon: bool, //false to only use icons guessed from the app
     pub respect_notification_timeout: bool,
+    pub pause_on_hover: bool,
+    pub hover_resume_timeout: f64, //in seconds, minimum time left after the pointer leaves
+    pub mouse: MouseBindings,
+    pub capabilities: CapabilityOverrides,
+    pub low: UrgencyOverride,
+    pub normal: UrgencyOverride,
+    pub critical: UrgencyOverride,
 }
 
 impl Default for NotificationConfig {
@@ -53,10 +85,10 @@ impl Default for NotificationConfig {
             enable: true,
             location: iced_layershell::reexport::Anchor::Top
                 | iced_layershell::reexport::Anchor::Right,
-            local_expire_timeout: 7,
+            local_expire_timeout: 7.0,
             max_notifications: 5,
-            height: 85, // to be min 65
-            width: 400, // to be min 300
+            height: 85, // min 65
+            width: 400, // min 300
             vertical_margin: 10,
             horizontal_margin: 10,
             border_radius: iced::border::radius(10.0),
@@ -65,16 +97,846 @@ impl Default for NotificationConfig {
             primary_text_color: iced::Color::parse("#e7d4a2").unwrap(),
             secondary_text_color: iced::Color::parse("#e7d4a2").unwrap(),
             background_color: iced::Color::parse("#282828").unwrap(),
-            respect_notification_icon: false,
+            respect_notification_icon: true,
             respect_notification_timeout: false,
+            pause_on_hover: true,
+            hover_resume_timeout: 2.0,
+            mouse: MouseBindings::default(),
+            capabilities: CapabilityOverrides::default(),
+            low: UrgencyOverride::default(),
+            normal: UrgencyOverride::default(),
+            critical: UrgencyOverride {
+                border_color: iced::Color::parse("#CC241D"),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl NotificationConfig {
+    pub fn urgency(&self, urgency: crate::data::notification::Urgency) -> &UrgencyOverride {
+        match urgency {
+            crate::data::notification::Urgency::Low => &self.low,
+            crate::data::notification::Urgency::Normal => &self.normal,
+            crate::data::notification::Urgency::Critical => &self.critical,
+        }
+    }
+
+    pub fn style(&self, urgency: crate::data::notification::Urgency) -> NotificationStyle {
+        let urgency = self.urgency(urgency);
+        NotificationStyle {
+            border_color: urgency.border_color.unwrap_or(self.border_color),
+            background_color: urgency.background_color.unwrap_or(self.background_color),
+            primary_text_color: urgency
+                .primary_text_color
+                .unwrap_or(self.primary_text_color),
+            secondary_text_color: urgency
+                .secondary_text_color
+                .unwrap_or(self.secondary_text_color),
+        }
+    }
+
+    /// What `GetCapabilities` reports: the features that are implemented and enabled,
+    /// adjusted by `[notifications.capabilities]`. `persistence` is up to `[history]`.
+    pub fn capabilities(&self, persistence: bool) -> Vec<String> {
+        let mut capabilities: Vec<String> = [
+            "actions",
+            "body",
+            "body-hyperlinks",
+            "body-markup",
+            "icon-static",
+        ]
+        .into_iter()
+        .chain(persistence.then_some("persistence"))
+        .map(String::from)
+        .collect();
+        capabilities.retain(|capability| !self.capabilities.remove.contains(capability));
+        for capability in &self.capabilities.add {
+            if !capabilities.contains(capability) {
+                capabilities.push(capability.clone());
+            }
+        }
+        capabilities
+    }
+
+    /// How long the notification stays on screen, `None` if it stays until dismissed.
+    /// The sender's `expire_timeout` is in milliseconds, -1 leaves the choice to us and 0
+    /// asks to never expire, which is always honored. A positive timeout is only used with
+    /// `respect_notification_timeout`. Critical notifications ignore the sender's timeout and
+    /// only expire when `[notifications.critical]` sets one.
+    pub fn expire_timeout(
+        &self,
+        notification: &crate::data::notification::Notification,
+    ) -> Option<std::time::Duration> {
+        let urgency = notification.hints.urgency;
+        if notification.expire_timeout == 0 {
+            return None;
+        }
+        if self.respect_notification_timeout
+            && notification.expire_timeout > 0
+            && urgency != crate::data::notification::Urgency::Critical
+        {
+            return Some(std::time::Duration::from_millis(
+                notification.expire_timeout as u64,
+            ));
+        }
+        let seconds = match self.urgency(urgency).expire_timeout {
+            Some(seconds) => seconds,
+            None if urgency == crate::data::notification::Urgency::Critical => return None,
+            None => self.local_expire_timeout,
+        };
+        std::time::Duration::try_from_secs_f64(seconds)
+            .ok()
+            .filter(|timeout| !timeout.is_zero())
+    }
+}
+
+/// `[notifications.low]`, `[notifications.normal]` and `[notifications.critical]`, keys left
+/// out fall back to the values of `[notifications]`
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrgencyOverride {
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub border_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub background_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub primary_text_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub secondary_text_color: Option<iced::Color>,
+    pub expire_timeout: Option<f64>, //in seconds, 0 to never expire
+}
+
+/// `[notifications.capabilities]`, for clients that behave better with a different answer
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CapabilityOverrides {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+/// `[notifications.mouse]`, what a click or scroll over a notification does
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseBindings {
+    pub left: MouseAction,
+    pub middle: MouseAction,
+    pub right: MouseAction,
+    pub scroll_up: MouseAction,
+    pub scroll_down: MouseAction,
+}
+
+impl Default for MouseBindings {
+    fn default() -> Self {
+        Self {
+            left: MouseAction::InvokeDefault,
+            middle: MouseAction::None,
+            right: MouseAction::Dismiss,
+            scroll_up: MouseAction::None,
+            scroll_down: MouseAction::None,
+        }
+    }
+}
+
+impl MouseBindings {
+    pub fn action(&self, trigger: MouseTrigger) -> MouseAction {
+        match trigger {
+            MouseTrigger::Left => self.left,
+            MouseTrigger::Middle => self.middle,
+            MouseTrigger::Right => self.right,
+            MouseTrigger::ScrollUp => self.scroll_up,
+            MouseTrigger::ScrollDown => self.scroll_down,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MouseAction {
+    None,
+    Dismiss,
+    DismissAll,
+    InvokeDefault,
+    OpenContextMenu,
+    CopyBody,
+    ToggleExpand,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseTrigger {
+    Left,
+    Middle,
+    Right,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// `[history]`, notifications kept after they are closed
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub enable: bool,
+    pub persist: bool, //keep the history across restarts
+    pub max_entries: usize,
+    pub per_app: std::collections::HashMap<String, usize>, //app name to entries kept, 0 for none
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            persist: true,
+            max_entries: 200,
+            per_app: std::collections::HashMap::new(),
+        }
+    }
+}
+
+/// `[center]`, the notification center panel listing the history
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CenterConfig {
+    pub width: u32,
+    pub height: u32, //0 to fill the output, needs both top and bottom anchors
+    #[serde(deserialize_with = "deserialize_anchor")]
+    pub location: iced_layershell::reexport::Anchor,
+    pub margin: i32,
+    #[serde(deserialize_with = "deserialize_layer")]
+    pub layer: iced_layershell::reexport::Layer,
+}
+
+impl Default for CenterConfig {
+    fn default() -> Self {
+        Self {
+            width: 420,
+            height: 0,
+            location: iced_layershell::reexport::Anchor::Top
+                | iced_layershell::reexport::Anchor::Bottom
+                | iced_layershell::reexport::Anchor::Right,
+            margin: 10,
+            layer: iced_layershell::reexport::Layer::Top,
+        }
+    }
+}
+
+/// `[dnd]`, do not disturb holds popups back, notifications still reach the history
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DndConfig {
+    pub enable: bool, //start with do not disturb on
+    pub schedule: Vec<DndSchedule>,
+    pub allow: Vec<String>, //app names or desktop entries that still pop up
+    pub allow_critical: bool,
+}
+
+impl Default for DndConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            schedule: Vec::new(),
+            allow: Vec::new(),
+            allow_critical: true,
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
+impl DndConfig {
+    /// Whether a notification pops up even while do not disturb is on
+    pub fn allows(&self, notification: &crate::data::notification::Notification) -> bool {
+        (self.allow_critical
+            && notification.hints.urgency == crate::data::notification::Urgency::Critical)
+            || self.allow.iter().any(|allowed| {
+                allowed.eq_ignore_ascii_case(&notification.app_name)
+                    || notification
+                        .hints
+                        .desktop_entry
+                        .as_deref()
+                        .is_some_and(|entry| allowed.eq_ignore_ascii_case(entry))
+            })
+    }
+
+    /// Whether `now` falls into one of the scheduled periods
+    pub fn scheduled(&self, now: &jiff::Zoned) -> bool {
+        self.schedule.iter().any(|period| period.contains(now))
+    }
+}
+
+/// `[[dnd.schedule]]`, a daily period, `to` before `from` runs past midnight
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DndSchedule {
+    #[serde(deserialize_with = "deserialize_clock_time")]
+    pub from: u16, //minutes since midnight
+    #[serde(deserialize_with = "deserialize_clock_time")]
+    pub to: u16,
+    #[serde(default)]
+    pub days: Vec<Weekday>, //days the period starts on, empty for every day
+}
+
+impl DndSchedule {
+    fn contains(&self, now: &jiff::Zoned) -> bool {
+        let minute = now.hour() as u16 * 60 + now.minute() as u16;
+        let starts_on = |weekday: jiff::civil::Weekday| {
+            self.days.is_empty() || self.days.iter().any(|day| day.0 == weekday)
+        };
+        if self.from <= self.to {
+            starts_on(now.weekday()) && (self.from..self.to).contains(&minute)
+        } else {
+            // the part after midnight belongs to the period that started the day before
+            (minute >= self.from && starts_on(now.weekday()))
+                || (minute < self.to && starts_on(now.weekday().previous()))
+        }
+    }
+}
+
+/// "mon" to "sun", full names work too
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weekday(pub jiff::civil::Weekday);
+
+impl<'de> serde::Deserialize<'de> for Weekday {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use jiff::civil::Weekday;
+
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let weekday = match value.trim().to_lowercase().as_str() {
+            "mon" | "monday" => Weekday::Monday,
+            "tue" | "tuesday" => Weekday::Tuesday,
+            "wed" | "wednesday" => Weekday::Wednesday,
+            "thu" | "thursday" => Weekday::Thursday,
+            "fri" | "friday" => Weekday::Friday,
+            "sat" | "saturday" => Weekday::Saturday,
+            "sun" | "sunday" => Weekday::Sunday,
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid day \"{value}\", expected mon, tue, wed, thu, fri, sat or sun"
+                )));
+            }
+        };
+        Ok(Self(weekday))
+    }
+}
+
+/// `[[rules]]`, a rule applies its actions to every notification matching all of its
+/// conditions, conditions left out match anything. Later rules override earlier ones.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    pub app_name: Option<String>, //case insensitive, like desktop_entry and category
+    pub desktop_entry: Option<String>,
+    #[serde(deserialize_with = "deserialize_optional_regex")]
+    pub summary: Option<regex::Regex>,
+    #[serde(deserialize_with = "deserialize_optional_regex")]
+    pub body: Option<regex::Regex>, //matched against the body without markup
+    pub category: Option<String>,
+    pub urgency: Option<crate::data::notification::Urgency>,
+
+    pub drop: bool,                  //closed right away, not even kept in the history
+    pub mute: bool,                  //no popup, still kept in the history
+    pub expire_timeout: Option<f64>, //in seconds, 0 to never expire
+    pub set_urgency: Option<crate::data::notification::Urgency>,
+    pub icon: Option<String>, //icon name or path
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub border_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub background_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub primary_text_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub secondary_text_color: Option<iced::Color>,
+    #[serde(deserialize_with = "deserialize_optional_anchor")]
+    pub location: Option<iced_layershell::reexport::Anchor>,
+    pub exec: Vec<String>, //command and its arguments, run without a shell
+}
+
+impl Rule {
+    pub fn matches(&self, notification: &crate::data::notification::Notification) -> bool {
+        let same = |expected: &Option<String>, value: Option<&str>| {
+            expected.as_deref().is_none_or(|expected| {
+                value.is_some_and(|value| expected.eq_ignore_ascii_case(value))
+            })
+        };
+        same(&self.app_name, Some(&notification.app_name))
+            && same(
+                &self.desktop_entry,
+                notification.hints.desktop_entry.as_deref(),
+            )
+            && same(&self.category, notification.hints.category.as_deref())
+            && self
+                .urgency
+                .is_none_or(|urgency| urgency == notification.hints.urgency)
+            && self
+                .summary
+                .as_ref()
+                .is_none_or(|summary| summary.is_match(&notification.summary))
+            && self.body.as_ref().is_none_or(|body| {
+                body.is_match(&crate::data::markup::plain_text(&notification.body))
+            })
+    }
+}
+
+/// `[hooks]`, commands run on notification events. The notification is written to their
+/// stdin as JSON and set in `IWWC_*` environment variables.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    pub on_notify: Vec<String>, //command and its arguments, run without a shell
+    pub on_close: Vec<String>,
+    pub on_action: Vec<String>,
+    pub timeout: f64, //in seconds before a hook is killed, 0 to let it run
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            on_notify: Vec::new(),
+            on_close: Vec::new(),
+            on_action: Vec::new(),
+            timeout: 10.0,
+        }
+    }
+}
+
+/// Colors of a notification after the urgency overrides are applied
+#[derive(Debug, Clone, Copy)]
+pub struct NotificationStyle {
+    pub border_color: iced::Color,
+    pub background_color: iced::Color,
+    pub primary_text_color: iced::Color,
+    pub secondary_text_color: iced::Color,
+}
+
+#[derive(Default, Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub global: Global,
     pub notifications: NotificationConfig,
+    pub history: HistoryConfig,
+    pub center: CenterConfig,
+    pub dnd: DndConfig,
+    pub rules: Vec<Rule>,
+    pub hooks: HooksConfig,
     #[allow(dead_code)]
     pub widgets: Vec<WidgetWindow>,
 }
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/iwwc/config.toml`, falling back to `~/.config/iwwc/config.toml`
+    pub fn default_path() -> std::path::PathBuf {
+        let config_home = std::env::var("XDG_CONFIG_HOME")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| std::env::var("HOME").unwrap_or_default() + "/.config");
+        std::path::PathBuf::from(config_home)
+            .join("iwwc")
+            .join("config.toml")
+    }
+
+    /// Reads the config from `path` (or the default location). Keys missing from the file keep
+    /// their default values, a missing default file is not an error.
+    pub fn load(
+        path: Option<&std::path::Path>,
+    ) -> Result<Self, crate::handler::error::ConfigError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = Self::default_path();
+                if !path.exists() {
+                    log::info!("No config found at {path:?}, using defaults");
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+
+        let content =
+            std::fs::read_to_string(&path).map_err(|e| crate::handler::error::ConfigError::Io {
+                path: path.clone(),
+                error: e,
+            })?;
+        let (config, diagnostics) = crate::data::validation::check(&content);
+        match config {
+            Some(config) => {
+                for diagnostic in &diagnostics {
+                    log::warn!("{}:{diagnostic}", path.display());
+                }
+                log::info!("Config loaded from {path:?}");
+                Ok(config)
+            }
+            None => Err(crate::handler::error::ConfigError::Invalid { path, diagnostics }),
+        }
+    }
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<iced::Color, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_color(&value).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "invalid color \"{value}\", expected \"#rgb\", \"#rgba\", \"#rrggbb\" or \"#rrggbbaa\""
+        ))
+    })
+}
+
+fn deserialize_optional_color<'de, D>(deserializer: D) -> Result<Option<iced::Color>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_color(deserializer).map(Some)
+}
+
+fn deserialize_optional_regex<'de, D>(deserializer: D) -> Result<Option<regex::Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_regex(&value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// The error is cut down to its last line, the full one points at the spot over several lines
+pub fn parse_regex(value: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(value).map_err(|e| {
+        let e = e.to_string();
+        let reason = e.lines().last().unwrap_or_default();
+        format!(
+            "invalid regex \"{value}\": {}",
+            reason.strip_prefix("error: ").unwrap_or(reason)
+        )
+    })
+}
+
+pub fn parse_color(value: &str) -> Option<iced::Color> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channels: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).unwrap() as u8 * 17)
+            .collect(),
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect(),
+        _ => return None,
+    };
+    let alpha = channels.get(3).map_or(1.0, |a| *a as f32 / 255.0);
+    Some(iced::Color::from_rgba8(
+        channels[0],
+        channels[1],
+        channels[2],
+        alpha,
+    ))
+}
+
+fn deserialize_clock_time<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_clock_time(&value).ok_or_else(|| {
+        serde::de::Error::custom(format!("invalid time \"{value}\", expected \"HH:MM\""))
+    })
+}
+
+/// "HH:MM" in 24 hour format to minutes since midnight, "24:00" is the end of the day
+pub fn parse_clock_time(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    if minutes >= 60 || hours > 24 || (hours == 24 && minutes > 0) {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+fn deserialize_anchor<'de, D>(
+    deserializer: D,
+) -> Result<iced_layershell::reexport::Anchor, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_anchor(&value).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "invalid anchor \"{value}\", expected a combination of top, bottom, left and right \
+             (e.g. \"top-right\") or \"center\""
+        ))
+    })
+}
+
+fn deserialize_optional_anchor<'de, D>(
+    deserializer: D,
+) -> Result<Option<iced_layershell::reexport::Anchor>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_anchor(deserializer).map(Some)
+}
+
+/// Accepts "top-right", "top|right", "top right", "center" and friends
+pub fn parse_anchor(value: &str) -> Option<iced_layershell::reexport::Anchor> {
+    use iced_layershell::reexport::Anchor;
+
+    let value = value.trim().to_lowercase();
+    if value == "center" || value == "none" {
+        return Some(Anchor::empty());
+    }
+    let mut anchor = Anchor::empty();
+    for part in value
+        .split(['-', '|', ' ', ',', '_'])
+        .filter(|part| !part.is_empty())
+    {
+        anchor |= match part {
+            "top" => Anchor::Top,
+            "bottom" => Anchor::Bottom,
+            "left" => Anchor::Left,
+            "right" => Anchor::Right,
+            _ => return None,
+        };
+    }
+    if anchor.is_empty() {
+        None
+    } else {
+        Some(anchor)
+    }
+}
+
+fn deserialize_layer<'de, D>(deserializer: D) -> Result<iced_layershell::reexport::Layer, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_layer(&value).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "invalid layer \"{value}\", expected one of background, bottom, top, overlay"
+        ))
+    })
+}
+
+pub fn parse_layer(value: &str) -> Option<iced_layershell::reexport::Layer> {
+    use iced_layershell::reexport::Layer;
+
+    match value.trim().to_lowercase().as_str() {
+        "background" => Some(Layer::Background),
+        "bottom" => Some(Layer::Bottom),
+        "top" => Some(Layer::Top),
+        "overlay" => Some(Layer::Overlay),
+        _ => None,
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RadiusValue {
+    All(f32),
+    Corners([f32; 4]),
+    Named {
+        #[serde(default)]
+        top_left: f32,
+        #[serde(default)]
+        top_right: f32,
+        #[serde(default)]
+        bottom_right: f32,
+        #[serde(default)]
+        bottom_left: f32,
+    },
+}
+
+/// Either a single number, `[top_left, top_right, bottom_right, bottom_left]`
+/// or a table with the corner names
+fn deserialize_radius<'de, D>(deserializer: D) -> Result<iced::border::Radius, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <RadiusValue as serde::Deserialize>::deserialize(deserializer).map_err(|_| {
+        serde::de::Error::custom(
+            "invalid radius, expected a number, an array of 4 numbers or a table of corners",
+        )
+    })?;
+    Ok(match value {
+        RadiusValue::All(radius) => iced::border::radius(radius),
+        RadiusValue::Corners([top_left, top_right, bottom_right, bottom_left])
+        | RadiusValue::Named {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        } => iced::border::Radius {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::notification::Urgency;
+
+    fn notification(
+        urgency: Urgency,
+        expire_timeout: i32,
+    ) -> crate::data::notification::Notification {
+        crate::data::notification::Notification {
+            app_name: String::new(),
+            app_icon: String::new(),
+            replaces_id: 0,
+            summary: String::new(),
+            body: String::new(),
+            actions: Vec::new(),
+            expire_timeout,
+            notification_id: 1,
+            hints: crate::data::notification::Hints {
+                urgency,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn config(content: &str) -> NotificationConfig {
+        toml::from_str::<Config>(content).unwrap().notifications
+    }
+
+    #[test]
+    fn critical_never_expires_by_default() {
+        let config = config("");
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Critical, -1)),
+            None
+        );
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Normal, -1)),
+            Some(std::time::Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn restyled_critical_still_never_expires() {
+        let config = config("[notifications.critical]\nborder_color = \"#ff0000\"\n");
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Critical, -1)),
+            None
+        );
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Critical, 5000)),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_from_the_sender_never_expires() {
+        let config = config("");
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Normal, 0)),
+            None
+        );
+        assert_eq!(config.expire_timeout(&notification(Urgency::Low, 0)), None);
+    }
+
+    #[test]
+    fn positive_timeouts_need_respect_notification_timeout() {
+        let ignored = config("");
+        assert_eq!(
+            ignored.expire_timeout(&notification(Urgency::Normal, 1500)),
+            Some(std::time::Duration::from_secs(7))
+        );
+        let respected = config("[notifications]\nrespect_notification_timeout = true\n");
+        assert_eq!(
+            respected.expire_timeout(&notification(Urgency::Normal, 1500)),
+            Some(std::time::Duration::from_millis(1500))
+        );
+        assert_eq!(
+            respected.expire_timeout(&notification(Urgency::Normal, -1)),
+            Some(std::time::Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn critical_expires_when_configured() {
+        let config = config("[notifications.critical]\nexpire_timeout = 30\n");
+        assert_eq!(
+            config.expire_timeout(&notification(Urgency::Critical, -1)),
+            Some(std::time::Duration::from_secs(30))
+        );
+    }
+
+    fn dnd(content: &str) -> DndConfig {
+        toml::from_str::<Config>(content).unwrap().dnd
+    }
+
+    /// 2024-06-07 is a friday
+    fn at(time: &str) -> jiff::Zoned {
+        format!("{time}[UTC]").parse().unwrap()
+    }
+
+    #[test]
+    fn parses_clock_times() {
+        assert_eq!(parse_clock_time("00:00"), Some(0));
+        assert_eq!(parse_clock_time("7:05"), Some(425));
+        assert_eq!(parse_clock_time(" 22:30 "), Some(1350));
+        assert_eq!(parse_clock_time("24:00"), Some(1440));
+        assert_eq!(parse_clock_time("24:01"), None);
+        assert_eq!(parse_clock_time("12:60"), None);
+        assert_eq!(parse_clock_time("12"), None);
+        assert_eq!(parse_clock_time("-1:00"), None);
+        assert_eq!(parse_clock_time("ab:cd"), None);
+    }
+
+    #[test]
+    fn schedule_within_a_day() {
+        let config = dnd("[[dnd.schedule]]\nfrom = \"09:00\"\nto = \"17:00\"");
+        assert!(!config.scheduled(&at("2024-06-07T08:59")));
+        assert!(config.scheduled(&at("2024-06-07T09:00")));
+        assert!(config.scheduled(&at("2024-06-07T16:59")));
+        assert!(!config.scheduled(&at("2024-06-07T17:00")));
+    }
+
+    #[test]
+    fn schedule_past_midnight() {
+        let config = dnd("[[dnd.schedule]]\nfrom = \"22:00\"\nto = \"07:30\"");
+        assert!(!config.scheduled(&at("2024-06-07T21:59")));
+        assert!(config.scheduled(&at("2024-06-07T22:00")));
+        assert!(config.scheduled(&at("2024-06-08T00:00")));
+        assert!(config.scheduled(&at("2024-06-08T07:29")));
+        assert!(!config.scheduled(&at("2024-06-08T07:30")));
+        assert!(!config.scheduled(&at("2024-06-08T12:00")));
+    }
+
+    #[test]
+    fn schedule_days_are_the_days_it_starts_on() {
+        let config =
+            dnd("[[dnd.schedule]]\nfrom = \"22:00\"\nto = \"07:00\"\ndays = [\"fri\", \"Sunday\"]");
+        assert!(config.scheduled(&at("2024-06-07T23:00")));
+        // saturday morning still belongs to friday night
+        assert!(config.scheduled(&at("2024-06-08T06:00")));
+        assert!(!config.scheduled(&at("2024-06-08T23:00")));
+        assert!(!config.scheduled(&at("2024-06-09T06:00")));
+        assert!(config.scheduled(&at("2024-06-09T23:00")));
+        // sunday night runs into monday, across the end of the week
+        assert!(config.scheduled(&at("2024-06-10T06:00")));
+        assert!(!config.scheduled(&at("2024-06-06T06:00")));
+    }
+
+    #[test]
+    fn rejects_invalid_schedules() {
+        assert!(
+            toml::from_str::<Config>("[[dnd.schedule]]\nfrom = \"25:00\"\nto = \"07:00\"").is_err()
+        );
+        assert!(
+            toml::from_str::<Config>(
+                "[[dnd.schedule]]\nfrom = \"22:00\"\nto = \"07:00\"\ndays = [\"someday\"]"
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+/// `Icon=` values of every installed application, indexed by desktop file id and by `Name=`
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntries {
+    by_id: std::collections::HashMap<String, String>,
+    by_name: std::collections::HashMap<String, String>,
+}
+
+impl DesktopEntries {
+    pub fn load() -> Self {
+        let mut entries = Self::default();
+        for dir in application_dirs() {
+            entries.scan(&dir, "");
+        }
+        log::debug!("Indexed {} desktop entries with icons", entries.by_id.len());
+        entries
+    }
+
+    /// Desktop files in subdirectories get the directory as an id prefix,
+    /// `applications/kde/foo.desktop` has the id `kde-foo`
+    fn scan(&mut self, dir: &std::path::Path, prefix: &str) {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_dir() {
+                self.scan(&path, &format!("{prefix}{file_name}-"));
+                continue;
+            }
+            let Some(id) = file_name.strip_suffix(".desktop") else {
+                continue;
+            };
+            let id = format!("{prefix}{id}").to_lowercase();
+            // earlier directories take precedence, the same id later on is shadowed
+            if self.by_id.contains_key(&id) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let sections = crate::data::icons::parse_ini(&content);
+            let Some(desktop_entry) = sections.get("Desktop Entry") else {
+                continue;
+            };
+            if desktop_entry
+                .get("Hidden")
+                .is_some_and(|hidden| hidden == "true")
+            {
+                continue;
+            }
+            let Some(icon) = desktop_entry.get("Icon").filter(|icon| !icon.is_empty()) else {
+                continue;
+            };
+
+            self.by_id.insert(id, icon.clone());
+            for key in ["Name", "StartupWMClass"] {
+                if let Some(name) = desktop_entry.get(key) {
+                    self.by_name
+                        .entry(name.to_lowercase())
+                        .or_insert_with(|| icon.clone());
+                }
+            }
+        }
+    }
+
+    /// `desktop_entry` is the hint value, with or without the `.desktop` suffix
+    pub fn icon_for_id(&self, desktop_entry: &str) -> Option<&str> {
+        let id = desktop_entry
+            .strip_suffix(".desktop")
+            .unwrap_or(desktop_entry)
+            .to_lowercase();
+        self.by_id.get(&id).map(String::as_str)
+    }
+
+    pub fn icon_for_app_name(&self, app_name: &str) -> Option<&str> {
+        let name = app_name.to_lowercase();
+        self.by_name
+            .get(&name)
+            .or_else(|| self.by_id.get(&name))
+            .map(String::as_str)
+    }
+}
+
+/// `$XDG_DATA_HOME/applications`, `$XDG_DATA_DIRS/applications` and the flatpak exports,
+/// in order of precedence
+fn application_dirs() -> Vec<std::path::PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| home.clone() + "/.local/share");
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs: Vec<std::path::PathBuf> = std::iter::once(data_home.as_str())
+        .chain(data_dirs.split(':'))
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| std::path::PathBuf::from(dir).join("applications"))
+        .collect();
+    for flatpak in [
+        home + "/.local/share/flatpak/exports/share/applications",
+        "/var/lib/flatpak/exports/share/applications".to_string(),
+    ] {
+        let flatpak = std::path::PathBuf::from(flatpak);
+        if !dirs.contains(&flatpak) {
+            dirs.push(flatpak);
+        }
+    }
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
@@ -0,0 +1,589 @@
+/// A notification as it was shown, kept after it was closed
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct HistoryEntry {
+    /// unique across restarts, unlike notification ids
+    pub id: u64,
+    pub notification_id: u32,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<String>,
+    pub urgency: crate::data::notification::Urgency,
+    pub category: Option<String>,
+    pub desktop_entry: Option<String>,
+    pub image_path: Option<String>,
+    pub sound_name: Option<String>,
+    pub resident: bool,
+    /// file the icon was resolved to, icons sent as pixel data are not kept
+    pub icon: Option<std::path::PathBuf>,
+    /// unix timestamps in seconds
+    pub received: u64,
+    pub closed: Option<u64>,
+    pub close_reason: Option<crate::data::notification::CloseReason>,
+}
+
+impl HistoryEntry {
+    /// One line per entry for `iwwc history`: age, app, summary and the body without markup
+    pub fn to_line(&self, now: u64) -> String {
+        let body = crate::data::markup::plain_text(&self.body).replace(['\n', '\t'], " ");
+        format!(
+            "{}\t{}\t{}\t{}",
+            format_age(self.received, now),
+            self.app_name,
+            self.summary.replace(['\n', '\t'], " "),
+            body
+        )
+    }
+}
+
+/// Filters of a history query, every one left at `None` matches everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryQuery {
+    /// app name, case insensitive
+    pub app: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// searched case insensitively in the app name, summary and body
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Parses `key=value` arguments: `app=`, `text=`, `limit=` and `since=`/`until=` as a unix
+    /// timestamp or an age such as `30m`, `2h` or `1d`
+    pub fn parse(arguments: &[&str]) -> Result<Self, String> {
+        let now = now();
+        let mut query = Self::default();
+        for argument in arguments {
+            let Some((key, value)) = argument.split_once('=') else {
+                return Err(format!("expected key=value, got \"{argument}\""));
+            };
+            let time = || {
+                parse_time(value, now).ok_or_else(|| {
+                    format!("invalid time \"{value}\", expected a unix timestamp or e.g. 30m")
+                })
+            };
+            match key {
+                "app" => query.app = Some(value.to_string()),
+                "text" => query.text = Some(value.to_string()),
+                "since" => query.since = Some(time()?),
+                "until" => query.until = Some(time()?),
+                "limit" => {
+                    query.limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid limit \"{value}\""))?,
+                    )
+                }
+                _ => return Err(format!("unknown filter \"{key}\"")),
+            }
+        }
+        Ok(query)
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        if let Some(app) = &self.app {
+            if !entry.app_name.eq_ignore_ascii_case(app) {
+                return false;
+            }
+        }
+        if self.since.is_some_and(|since| entry.received < since)
+            || self.until.is_some_and(|until| entry.received > until)
+        {
+            return false;
+        }
+        match &self.text {
+            Some(text) => {
+                let text = text.to_lowercase();
+                [
+                    entry.app_name.clone(),
+                    entry.summary.clone(),
+                    crate::data::markup::plain_text(&entry.body),
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&text))
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Store {
+    #[serde(default)]
+    entries: Vec<HistoryEntry>,
+}
+
+/// Every notification that was shown, oldest first, bounded by `[history]` and kept in
+/// `$XDG_STATE_HOME/iwwc/history.toml` when `persist` is set
+#[derive(Debug)]
+pub struct History {
+    entries: std::collections::VecDeque<HistoryEntry>,
+    config: crate::data::config::HistoryConfig,
+    next_id: u64,
+    /// bumped on every change, snapshots carry it so an older one never overwrites a newer
+    revision: u64,
+    saved_revision: u64,
+    /// the store is read once persisting starts, the entries in memory are newer after that
+    store_read: bool,
+}
+
+impl History {
+    /// Reads the stored history when `persist` is set
+    pub fn load(config: crate::data::config::HistoryConfig) -> Self {
+        let store_read = config.enable && config.persist;
+        let entries = if store_read {
+            read_store()
+        } else {
+            std::collections::VecDeque::new()
+        };
+        let next_id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1);
+
+        let mut history = Self {
+            entries,
+            config,
+            next_id,
+            revision: 0,
+            saved_revision: 0,
+            store_read,
+        };
+        history.trim();
+        history
+    }
+
+    /// Applies a reloaded `[history]`, lowered limits drop the oldest entries right away.
+    /// Turning `persist` on reads the store first so it is added to instead of overwritten.
+    pub fn set_config(&mut self, config: crate::data::config::HistoryConfig) {
+        self.config = config;
+        if self.config.enable && self.config.persist && !self.store_read {
+            self.store_read = true;
+            self.merge_stored(read_store());
+        }
+        self.trim();
+    }
+
+    /// Puts stored entries before the ones recorded since the start, under new ids as both
+    /// were numbered from 1
+    fn merge_stored(&mut self, mut stored: std::collections::VecDeque<HistoryEntry>) {
+        for entry in stored.iter_mut() {
+            entry.id = self.next_id;
+            self.next_id += 1;
+        }
+        stored.append(&mut self.entries);
+        self.entries = stored;
+        // written even if nothing was stored, the entries in memory are to be kept from now on
+        self.revision += 1;
+    }
+
+    /// Records a shown notification. A replacement updates the entry of the notification it
+    /// replaces while that one is still open. Transient notifications bypass the history as
+    /// the spec asks.
+    pub fn record(
+        &mut self,
+        notification: &crate::data::notification::Notification,
+        icon: Option<&std::path::Path>,
+    ) {
+        if !self.config.enable
+            || notification.hints.transient
+            || self.app_limit(&notification.app_name) == Some(0)
+        {
+            return;
+        }
+
+        let replaced = (notification.replaces_id != 0)
+            .then(|| self.open_entry(notification.notification_id))
+            .flatten();
+        let (id, received) = match replaced {
+            Some(index) => (self.entries[index].id, self.entries[index].received),
+            None => {
+                self.next_id += 1;
+                (self.next_id - 1, now())
+            }
+        };
+        let entry = HistoryEntry {
+            id,
+            notification_id: notification.notification_id,
+            app_name: notification.app_name.clone(),
+            app_icon: notification.app_icon.clone(),
+            summary: notification.summary.clone(),
+            body: notification.body.clone(),
+            actions: notification.actions.clone(),
+            urgency: notification.hints.urgency,
+            category: notification.hints.category.clone(),
+            desktop_entry: notification.hints.desktop_entry.clone(),
+            image_path: notification.hints.image_path.clone(),
+            sound_name: notification.hints.sound_name.clone(),
+            resident: notification.hints.resident,
+            icon: icon.map(std::path::Path::to_path_buf),
+            received,
+            closed: None,
+            close_reason: None,
+        };
+        match replaced {
+            Some(index) => self.entries[index] = entry,
+            None => self.entries.push_back(entry),
+        }
+        self.trim();
+        self.revision += 1;
+    }
+
+    /// Stamps the open entry of `notification_id` with when and why it was closed
+    pub fn close(&mut self, notification_id: u32, reason: crate::data::notification::CloseReason) {
+        if let Some(index) = self.open_entry(notification_id) {
+            let entry = &mut self.entries[index];
+            entry.closed = Some(now());
+            entry.close_reason = Some(reason);
+            self.revision += 1;
+        }
+    }
+
+    /// Matching entries, newest first
+    pub fn query(&self, query: &HistoryQuery) -> Vec<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Forgets a single entry, the notification itself is left alone
+    pub fn remove(&mut self, id: u64) {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        if self.entries.len() != before {
+            self.revision += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if !self.entries.is_empty() {
+            self.entries.clear();
+            self.revision += 1;
+        }
+    }
+
+    /// The store to write when something changed since the last snapshot
+    pub fn snapshot(&mut self) -> Option<Snapshot> {
+        if !self.config.enable || !self.config.persist || self.saved_revision == self.revision {
+            return None;
+        }
+        let store = Store {
+            entries: self.entries.iter().cloned().collect(),
+        };
+        let content = match toml::to_string(&store) {
+            Ok(content) => content,
+            Err(e) => {
+                log::error!("Failed to serialize the history: {e}");
+                return None;
+            }
+        };
+        self.saved_revision = self.revision;
+        Some(Snapshot {
+            path: store_path(),
+            revision: self.revision,
+            content,
+        })
+    }
+
+    fn open_entry(&self, notification_id: u32) -> Option<usize> {
+        self.entries.iter().rposition(|entry| {
+            entry.notification_id == notification_id && entry.close_reason.is_none()
+        })
+    }
+
+    fn app_limit(&self, app_name: &str) -> Option<usize> {
+        self.config
+            .per_app
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(app_name))
+            .map(|(_, limit)| *limit)
+    }
+
+    /// Drops the oldest entries over the per app limits, then over `max_entries`
+    fn trim(&mut self) {
+        let before = self.entries.len();
+        if !self.config.per_app.is_empty() {
+            let mut over: std::collections::HashMap<String, usize> =
+                std::collections::HashMap::new();
+            for entry in &self.entries {
+                *over.entry(entry.app_name.to_lowercase()).or_default() += 1;
+            }
+            for (app, count) in over.iter_mut() {
+                *count = count.saturating_sub(self.app_limit(app).unwrap_or(usize::MAX));
+            }
+            self.entries
+                .retain(|entry| match over.get_mut(&entry.app_name.to_lowercase()) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                });
+        }
+        while self.entries.len() > self.config.max_entries {
+            self.entries.pop_front();
+        }
+        if self.entries.len() != before {
+            self.revision += 1;
+        }
+    }
+}
+
+/// Serialized history on its way to disk
+#[derive(Debug)]
+pub struct Snapshot {
+    path: std::path::PathBuf,
+    revision: u64,
+    content: String,
+}
+
+/// Revision of the last snapshot written, snapshots are written on the blocking pool and may
+/// finish out of order
+static WRITTEN: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
+
+impl Snapshot {
+    /// Replaces the store through a temporary file, readable by the user only as it holds
+    /// whatever was sent in notifications. Blocking.
+    pub fn write(self) {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut written = match WRITTEN.lock() {
+            Ok(written) => written,
+            Err(e) => {
+                log::error!("Failed to save the history: {e}");
+                return;
+            }
+        };
+        if *written >= self.revision {
+            return;
+        }
+
+        let temporary = self.path.with_extension("toml.tmp");
+        let result = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .mode(0o600)
+                    .open(&temporary)
+            })
+            .and_then(|mut file| file.write_all(self.content.as_bytes()))
+            .and_then(|_| std::fs::rename(&temporary, &self.path));
+        match result {
+            Ok(()) => *written = self.revision,
+            Err(e) => log::error!("Failed to save the history to {:?}: {e}", self.path),
+        }
+    }
+}
+
+/// The entries in the store, entries that were still shown when the daemon stopped are
+/// marked as closed for an undefined reason
+fn read_store() -> std::collections::VecDeque<HistoryEntry> {
+    let path = store_path();
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return std::collections::VecDeque::new();
+        }
+        Err(e) => {
+            log::error!("Failed to read the history at {path:?}: {e}");
+            return std::collections::VecDeque::new();
+        }
+    };
+    let mut entries: std::collections::VecDeque<HistoryEntry> =
+        match toml::from_str::<Store>(&content) {
+            Ok(store) => store.entries.into(),
+            Err(e) => {
+                log::error!("Failed to parse the history at {path:?}: {e}");
+                return std::collections::VecDeque::new();
+            }
+        };
+    for entry in entries
+        .iter_mut()
+        .filter(|entry| entry.close_reason.is_none())
+    {
+        entry.close_reason = Some(crate::data::notification::CloseReason::Undefined);
+    }
+    entries
+}
+
+/// `$XDG_STATE_HOME/iwwc/history.toml`, falling back to `~/.local/state/iwwc/history.toml`
+pub fn store_path() -> std::path::PathBuf {
+    let state_home = std::env::var("XDG_STATE_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| std::env::var("HOME").unwrap_or_default() + "/.local/state");
+    std::path::PathBuf::from(state_home)
+        .join("iwwc")
+        .join("history.toml")
+}
+
+/// Seconds since the unix epoch
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// "now", "5m ago", "3h ago", "2d ago"
+pub fn format_age(timestamp: u64, now: u64) -> String {
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..60 => "now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// A unix timestamp, or an age like `90s`, `30m`, `2h` or `7d` counted back from `now`
+fn parse_time(value: &str, now: u64) -> Option<u64> {
+    if let Ok(timestamp) = value.parse() {
+        return Some(timestamp);
+    }
+    let unit = value.chars().last()?;
+    let amount: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        _ => return None,
+    };
+    Some(now.saturating_sub(amount.checked_mul(seconds)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, app_name: &str, summary: &str, body: &str, received: u64) -> HistoryEntry {
+        HistoryEntry {
+            id,
+            app_name: app_name.to_string(),
+            summary: summary.to_string(),
+            body: body.to_string(),
+            received,
+            ..Default::default()
+        }
+    }
+
+    fn history(entries: Vec<HistoryEntry>) -> History {
+        History {
+            next_id: entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1),
+            entries: entries.into(),
+            config: crate::data::config::HistoryConfig::default(),
+            revision: 0,
+            saved_revision: 0,
+            store_read: true,
+        }
+    }
+
+    #[test]
+    fn parses_queries() {
+        let query = HistoryQuery::parse(&["app=Firefox", "text=build", "limit=10", "since=100"]);
+        assert_eq!(
+            query,
+            Ok(HistoryQuery {
+                app: Some("Firefox".to_string()),
+                since: Some(100),
+                until: None,
+                text: Some("build".to_string()),
+                limit: Some(10),
+            })
+        );
+        assert_eq!(HistoryQuery::parse(&[]), Ok(HistoryQuery::default()));
+        // only the first `=` separates the key
+        assert_eq!(
+            HistoryQuery::parse(&["text=a=b"]).map(|query| query.text),
+            Ok(Some("a=b".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        assert!(HistoryQuery::parse(&["firefox"]).is_err());
+        assert!(HistoryQuery::parse(&["color=red"]).is_err());
+        assert!(HistoryQuery::parse(&["limit=-1"]).is_err());
+        assert!(HistoryQuery::parse(&["since=yesterday"]).is_err());
+        assert!(HistoryQuery::parse(&["until="]).is_err());
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("1700000000", 0), Some(1_700_000_000));
+        assert_eq!(parse_time("90s", 1000), Some(910));
+        assert_eq!(parse_time("30m", 10_000), Some(8200));
+        assert_eq!(parse_time("2h", 10_000), Some(2800));
+        assert_eq!(parse_time("1d", 100_000), Some(13_600));
+        // ages before the epoch stop at it
+        assert_eq!(parse_time("7d", 1000), Some(0));
+        assert_eq!(parse_time("5w", 1000), None);
+        assert_eq!(parse_time("m", 1000), None);
+        assert_eq!(parse_time("-5m", 1000), None);
+        assert_eq!(parse_time("", 1000), None);
+        assert_eq!(parse_time(&format!("{}d", u64::MAX), 1000), None);
+    }
+
+    #[test]
+    fn formats_ages() {
+        assert_eq!(format_age(100, 130), "now");
+        assert_eq!(format_age(0, 300), "5m ago");
+        assert_eq!(format_age(0, 7200), "2h ago");
+        assert_eq!(format_age(0, 86400 * 3), "3d ago");
+        assert_eq!(format_age(200, 100), "now");
+    }
+
+    #[test]
+    fn queries_filter_newest_first() {
+        let history = history(vec![
+            entry(1, "Firefox", "Download done", "", 100),
+            entry(2, "Slack", "Message", "the <b>build</b> failed", 200),
+            entry(3, "firefox", "Build", "", 300),
+        ]);
+        let ids = |query: &HistoryQuery| -> Vec<u64> {
+            history.query(query).iter().map(|entry| entry.id).collect()
+        };
+        assert_eq!(ids(&HistoryQuery::default()), [3, 2, 1]);
+        assert_eq!(ids(&HistoryQuery::parse(&["app=FIREFOX"]).unwrap()), [3, 1]);
+        assert_eq!(ids(&HistoryQuery::parse(&["text=BUILD"]).unwrap()), [3, 2]);
+        assert_eq!(
+            ids(&HistoryQuery::parse(&["since=150", "until=250"]).unwrap()),
+            [2]
+        );
+        assert_eq!(ids(&HistoryQuery::parse(&["limit=1"]).unwrap()), [3]);
+    }
+
+    #[test]
+    fn stored_entries_go_first_under_new_ids() {
+        let mut history = history(vec![entry(1, "Slack", "new", "", 200)]);
+        let revision = history.revision;
+        history.merge_stored(
+            [
+                entry(1, "Firefox", "oldest", "", 50),
+                entry(2, "Firefox", "older", "", 100),
+            ]
+            .into(),
+        );
+        let entries: Vec<(u64, &str)> = history
+            .entries
+            .iter()
+            .map(|entry| (entry.id, entry.summary.as_str()))
+            .collect();
+        assert_eq!(entries, [(2, "oldest"), (3, "older"), (1, "new")]);
+        assert_eq!(history.next_id, 4);
+        assert!(history.revision > revision);
+    }
+}
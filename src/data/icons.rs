@@ -1,7 +1,10 @@
-fn default_icon() {
+/// Lookup order inside a directory, as listed by the Icon Theme Specification
+const ICON_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+pub fn default_icon() {
     const DEFAULT_ICON: &[u8] = include_bytes!("../../assets/testing/default.svg");
 
-    let path = std::env::var("HOME").unwrap() + "/.config/iwwc";
+    let path = std::env::var("HOME").unwrap_or_default() + "/.config/iwwc";
     if !std::path::Path::new(&path).exists() {
         if let Err(e) = std::fs::create_dir_all(&path) {
             log::error!("Failed to create a default icon directory: {e}");
@@ -19,109 +22,772 @@ fn default_icon() {
     }
 }
 
-pub fn get_system_icons_paths() -> std::collections::HashMap<String, std::path::PathBuf> {
-    let mut icons = std::collections::HashMap::new();
-    default_icon();
-    let home_dir = std::env::var("HOME").unwrap();
-    let icon_theme_name = {
-        if std::path::Path::new(&(home_dir.clone() + "/.config/gtk-4.0/settings.ini")).exists() {
-            let settings =
-                std::fs::read_to_string(home_dir + "/.config/gtk-4.0/settings.ini").unwrap();
-            let icon_theme = settings
-                .lines()
-                .find(|line| line.starts_with("gtk-icon-theme-name"))
-                .unwrap();
-            icon_theme.split('=').collect::<Vec<&str>>()[1]
-                .trim()
-                .to_string()
-        } else if std::path::Path::new(&(home_dir.clone() + "/.config/gtk-3.0/settings.ini"))
-            .exists()
-        {
-            let settings =
-                std::fs::read_to_string(home_dir + "/.config/gtk-3.0/settings.ini").unwrap();
-            let icon_theme = settings
-                .lines()
-                .find(|line| line.starts_with("gtk-icon-theme-name"))
-                .unwrap();
-            icon_theme.split('=').collect::<Vec<&str>>()[1]
-                .trim()
-                .to_string()
-        } else {
-            "Adwaita".to_string()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+#[derive(Debug, Clone)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    scale: u32,
+    kind: DirectoryType,
+}
+
+impl ThemeDirectory {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
         }
-    };
-    let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or("/usr/share".to_string());
-    let data_dirs = data_dirs.split(':').collect::<Vec<&str>>();
-    let mut icons_dir = None;
-    for dir in data_dirs {
-        if std::path::Path::new(&(dir.to_string() + "/icons/" + &icon_theme_name)).exists() {
-            icons_dir = Some(dir.to_string() + "/icons/" + &icon_theme_name);
-            break;
+        match self.kind {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryType::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
         }
     }
-    if icons_dir.is_none() {
-        log::warn!("Icon theme {icon_theme_name} not found, default icons will be used everywhere");
-        return icons;
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let requested = size * scale;
+        let (min, max) = match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        if requested < min * self.scale {
+            min * self.scale - requested
+        } else {
+            requested.saturating_sub(max * self.scale)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IconTheme {
+    name: String,
+    /// every `<base dir>/<theme name>` that exists, a theme may be spread over several
+    roots: Vec<std::path::PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+impl IconTheme {
+    fn load(name: &str, base_dirs: &[std::path::PathBuf]) -> Option<Self> {
+        let roots: Vec<std::path::PathBuf> = base_dirs
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+        let index = roots
+            .iter()
+            .find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())?;
+        let sections = parse_ini(&index);
+        let main = sections.get("Icon Theme")?;
+
+        let list = |key: &str| -> Vec<String> {
+            main.get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|item| item.trim().to_string())
+                        .filter(|item| !item.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut directory_names = list("Directories");
+        directory_names.extend(list("ScaledDirectories"));
+        let directories = directory_names
+            .into_iter()
+            .filter_map(|path| {
+                let section = sections.get(&path)?;
+                let number =
+                    |key: &str| section.get(key).and_then(|v| v.trim().parse::<u32>().ok());
+                let size = number("Size")?;
+                let kind = match section.get("Type").map(|v| v.trim()) {
+                    Some("Fixed") => DirectoryType::Fixed,
+                    Some("Scalable") => DirectoryType::Scalable,
+                    _ => DirectoryType::Threshold,
+                };
+                Some(ThemeDirectory {
+                    size,
+                    min_size: number("MinSize").unwrap_or(size),
+                    max_size: number("MaxSize").unwrap_or(size),
+                    threshold: number("Threshold").unwrap_or(2),
+                    scale: number("Scale").unwrap_or(1).max(1),
+                    kind,
+                    path,
+                })
+            })
+            .collect();
+
+        Some(Self {
+            name: name.to_string(),
+            roots,
+            directories,
+            inherits: list("Inherits"),
+        })
     }
 
-    if let Some(folders) = find_folders_recursively(&icons_dir.unwrap().into(), "apps") {
-        for folder in folders {
-            //find_icons_recursively(&folder).map(|i| icons.extend(i));
-            if let Some(i) = find_icons_recursively(&folder) {
-                icons.extend(i)
+    fn lookup(&self, icon: &str, size: u32, scale: u32) -> Option<std::path::PathBuf> {
+        for directory in &self.directories {
+            if !directory.matches_size(size, scale) {
+                continue;
+            }
+            if let Some(path) = self.find_in(directory, icon) {
+                return Some(path);
+            }
+        }
+
+        let mut best: Option<(u32, std::path::PathBuf)> = None;
+        for directory in &self.directories {
+            let distance = directory.size_distance(size, scale);
+            if best
+                .as_ref()
+                .is_some_and(|(best_distance, _)| distance >= *best_distance)
+            {
+                continue;
+            }
+            if let Some(path) = self.find_in(directory, icon) {
+                best = Some((distance, path));
             }
         }
+        best.map(|(_, path)| path)
     }
-    icons
+
+    fn find_in(&self, directory: &ThemeDirectory, icon: &str) -> Option<std::path::PathBuf> {
+        self.roots.iter().find_map(|root| {
+            ICON_EXTENSIONS.iter().find_map(|ext| {
+                let path = root.join(&directory.path).join(format!("{icon}.{ext}"));
+                path.is_file().then_some(path)
+            })
+        })
+    }
+}
+
+/// Icon Theme Specification lookup: the user theme, its `Inherits=` chain, hicolor and at
+/// last the unthemed icons in the base directories and `/usr/share/pixmaps`
+#[derive(Debug, Clone)]
+pub struct IconLookup {
+    base_dirs: Vec<std::path::PathBuf>,
+    pixmaps_dir: std::path::PathBuf,
+    /// the user theme followed by everything it inherits from, hicolor always last
+    themes: Vec<IconTheme>,
 }
 
-fn find_folders_recursively(
-    init_path: &std::path::PathBuf,
-    folder_name: &str,
-) -> Option<Vec<std::path::PathBuf>> {
-    let mut folders: Vec<std::path::PathBuf> = vec![];
-    for entry in std::fs::read_dir(init_path).unwrap() {
-        let entry = entry.unwrap();
-        let mut path = entry.path();
-        if path.is_symlink() {
-            path = std::fs::canonicalize(path).unwrap();
+impl IconLookup {
+    pub fn load() -> Self {
+        let theme_name = user_theme_name().unwrap_or_else(|| "Adwaita".to_string());
+        log::info!("Using icon theme {theme_name}");
+        Self::new(
+            theme_name,
+            base_dirs(),
+            std::path::PathBuf::from("/usr/share/pixmaps"),
+        )
+    }
+
+    fn new(
+        theme_name: String,
+        base_dirs: Vec<std::path::PathBuf>,
+        pixmaps_dir: std::path::PathBuf,
+    ) -> Self {
+        let mut themes: Vec<IconTheme> = Vec::new();
+        // depth first, parents are searched in the order they are listed
+        let mut stack = vec![theme_name];
+        while let Some(name) = stack.pop() {
+            if themes.iter().any(|theme| theme.name == name) {
+                continue;
+            }
+            match IconTheme::load(&name, &base_dirs) {
+                Some(theme) => {
+                    stack.extend(theme.inherits.iter().rev().cloned());
+                    themes.push(theme);
+                }
+                None => log::warn!("Icon theme {name} not found"),
+            }
         }
-        if path.is_dir() {
-            if path.file_name().unwrap().to_str().unwrap() == folder_name {
-                folders.push(path);
-            } else if let Some(mut sub_folders) = find_folders_recursively(&path, folder_name) {
-                folders.append(&mut sub_folders);
+        // the spec requires hicolor as the final fallback no matter what the themes inherit
+        if let Some(position) = themes.iter().position(|theme| theme.name == "hicolor") {
+            let hicolor = themes.remove(position);
+            themes.push(hicolor);
+        } else if let Some(hicolor) = IconTheme::load("hicolor", &base_dirs) {
+            themes.push(hicolor);
+        }
+
+        Self {
+            base_dirs,
+            pixmaps_dir,
+            themes,
+        }
+    }
+
+    /// Finds the best match for `icon` at `size` pixels (scale 1)
+    pub fn find_icon(&self, icon: &str, size: u32) -> Option<std::path::PathBuf> {
+        if icon.is_empty() || icon.contains('/') {
+            return None;
+        }
+        self.themes
+            .iter()
+            .find_map(|theme| theme.lookup(icon, size, 1))
+            .or_else(|| self.find_unthemed(icon))
+    }
+
+    fn find_unthemed(&self, icon: &str) -> Option<std::path::PathBuf> {
+        self.base_dirs
+            .iter()
+            .map(|dir| dir.as_path())
+            .chain(std::iter::once(self.pixmaps_dir.as_path()))
+            .find_map(|dir| {
+                ICON_EXTENSIONS.iter().find_map(|ext| {
+                    let path = dir.join(format!("{icon}.{ext}"));
+                    path.is_file().then_some(path)
+                })
+            })
+    }
+
+    /// Every directory an icon may be looked up in, any change to them is reflected in
+    /// their modification time
+    fn watched_dirs(&self) -> Vec<std::path::PathBuf> {
+        let mut dirs: Vec<std::path::PathBuf> = self.base_dirs.clone();
+        dirs.push(self.pixmaps_dir.clone());
+        for theme in &self.themes {
+            for root in &theme.roots {
+                dirs.push(root.clone());
+                dirs.extend(
+                    theme
+                        .directories
+                        .iter()
+                        .map(|directory| root.join(&directory.path)),
+                );
             }
         }
+        dirs
     }
-    if folders.is_empty() {
-        None
-    } else {
-        Some(folders)
-    }
-}
-
-fn find_icons_recursively(
-    init_path: &std::path::PathBuf,
-) -> Option<std::collections::HashMap<String, std::path::PathBuf>> {
-    let mut icons = std::collections::HashMap::new();
-    for entry in std::fs::read_dir(init_path).unwrap() {
-        let entry = entry.unwrap();
-        if entry.file_type().unwrap().is_file() {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "svg" {
-                    icons.insert(
-                        path.file_stem().unwrap().to_str().unwrap().to_string(),
-                        path,
-                    );
+
+    /// Identifies the state of the icon directories, a cache written for a different
+    /// fingerprint is stale. The bytes hashed are spelled out so the value stays the same
+    /// across builds and toolchains.
+    fn fingerprint(&self) -> u64 {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut hasher = Fnv1a::default();
+        for theme in &self.themes {
+            hasher.write(theme.name.as_bytes());
+            hasher.write(&[0]);
+        }
+        for dir in self.watched_dirs() {
+            let modified = std::fs::metadata(&dir)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok());
+            hasher.write(dir.as_os_str().as_bytes());
+            hasher.write(&[0]);
+            match modified {
+                Some(modified) => {
+                    hasher.write(&[1]);
+                    hasher.write(&modified.as_secs().to_le_bytes());
+                    hasher.write(&modified.subsec_nanos().to_le_bytes());
                 }
+                None => hasher.write(&[0]),
+            }
+        }
+        hasher.0
+    }
+}
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` its output is fixed
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Format version and fingerprint scheme, bumped whenever either changes
+const CACHE_HEADER: &str = "iwwc-icons 2";
+
+/// Memoizing front of [`IconLookup`], hits and misses are kept in memory and appended to
+/// `$XDG_CACHE_HOME/iwwc/icons.cache`, which is reused until a theme directory changes and
+/// compacted every time it is loaded.
+/// Lookups touch the filesystem, so call it off the iced update loop.
+pub struct IconResolver {
+    lookup: IconLookup,
+    memo: std::sync::Mutex<std::collections::HashMap<(String, u32), Option<std::path::PathBuf>>>,
+    cache_file: std::sync::Mutex<Option<std::fs::File>>,
+}
+
+impl IconResolver {
+    pub fn load() -> Self {
+        let lookup = IconLookup::load();
+        let header = format!("{CACHE_HEADER} {:016x}", lookup.fingerprint());
+        let cache_path = cache_path();
+
+        let memo = std::fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|content| parse_cache(&content, &header))
+            .unwrap_or_default();
+        if !memo.is_empty() {
+            log::debug!("Loaded {} cached icon lookups", memo.len());
+        }
+        let cache_file = open_cache(&cache_path, &header, &memo)
+            .map_err(|e| log::warn!("Icon cache {} is not writable: {e}", cache_path.display()))
+            .ok();
+
+        Self {
+            lookup,
+            memo: std::sync::Mutex::new(memo),
+            cache_file: std::sync::Mutex::new(cache_file),
+        }
+    }
+
+    pub fn find_icon(&self, icon: &str, size: u32) -> Option<std::path::PathBuf> {
+        let key = (icon.to_string(), size);
+        if let Some(cached) = self
+            .memo
+            .lock()
+            .ok()
+            .and_then(|memo| memo.get(&key).cloned())
+        {
+            // a removed file does not always change the fingerprint, e.g. a deleted pixmap
+            match cached {
+                Some(path) if !path.is_file() => {}
+                cached => return cached,
             }
-        } else if entry.file_type().unwrap().is_dir() {
-            if let Some(sub_icons) = find_icons_recursively(&entry.path()) {
-                icons.extend(sub_icons);
+        }
+
+        let found = self.lookup.find_icon(icon, size);
+        self.remember(key, found.clone());
+        found
+    }
+
+    fn remember(&self, key: (String, u32), path: Option<std::path::PathBuf>) {
+        use std::io::Write;
+
+        let line = cache_line(&key, path.as_deref());
+        if let (Some(line), Ok(mut cache_file)) = (line, self.cache_file.lock()) {
+            if let Some(file) = cache_file.as_mut() {
+                if let Err(e) = file.write_all(line.as_bytes()) {
+                    log::warn!("Failed to write the icon cache: {e}");
+                    *cache_file = None;
+                }
             }
         }
+        if let Ok(mut memo) = self.memo.lock() {
+            memo.insert(key, path);
+        }
+    }
+}
+
+fn cache_path() -> std::path::PathBuf {
+    let cache_home = std::env::var("XDG_CACHE_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| std::env::var("HOME").unwrap_or_default() + "/.cache");
+    std::path::PathBuf::from(cache_home).join("iwwc/icons.cache")
+}
+
+/// `None` when the cache was written for other icon directories
+fn parse_cache(
+    content: &str,
+    header: &str,
+) -> Option<std::collections::HashMap<(String, u32), Option<std::path::PathBuf>>> {
+    let mut lines = content.lines();
+    if lines.next()? != header {
+        return None;
+    }
+    Some(
+        lines
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let size = fields.next()?.parse().ok()?;
+                let icon = fields.next()?.to_string();
+                let path = fields.next()?;
+                let path = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+                Some(((icon, size), path))
+            })
+            .collect(),
+    )
+}
+
+/// One `size\ticon\tpath` line, the path is left empty for a miss. Names with separators
+/// would break the format, they are memoized only.
+fn cache_line(key: &(String, u32), path: Option<&std::path::Path>) -> Option<String> {
+    match path {
+        Some(path) => path.to_str(),
+        None => Some(""),
+    }
+    .filter(|path| !path.contains('\n') && !key.0.contains(['\t', '\n']))
+    .map(|path| format!("{}\t{}\t{path}\n", key.1, key.0))
+}
+
+/// Rewrites the cache from the entries loaded out of it, which drops the duplicates earlier
+/// runs appended, and keeps it open for appending
+fn open_cache(
+    path: &std::path::Path,
+    header: &str,
+    memo: &std::collections::HashMap<(String, u32), Option<std::path::PathBuf>>,
+) -> std::io::Result<std::fs::File> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut content = format!("{header}\n");
+    for (key, path) in memo {
+        content.extend(cache_line(key, path.as_deref()));
+    }
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(file)
+}
+
+/// `~/.icons`, `$XDG_DATA_HOME/icons` and `$XDG_DATA_DIRS/icons`, in that order
+fn base_dirs() -> Vec<std::path::PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| home.clone() + "/.local/share");
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs = vec![
+        std::path::PathBuf::from(&home).join(".icons"),
+        std::path::PathBuf::from(data_home).join("icons"),
+    ];
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| std::path::PathBuf::from(dir).join("icons")),
+    );
+    dirs.dedup();
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
+
+fn user_theme_name() -> Option<String> {
+    let home = std::env::var("HOME").ok()?;
+    ["gtk-4.0", "gtk-3.0"].iter().find_map(|gtk| {
+        let settings =
+            std::fs::read_to_string(format!("{home}/.config/{gtk}/settings.ini")).ok()?;
+        parse_ini(&settings)
+            .get("Settings")?
+            .get("gtk-icon-theme-name")
+            .map(|name| name.trim().trim_matches('"').to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// Sections of a desktop-entry style ini file, keys of repeated sections are merged
+pub fn parse_ini(
+    content: &str,
+) -> std::collections::HashMap<String, std::collections::HashMap<String, String>> {
+    let mut sections: std::collections::HashMap<String, std::collections::HashMap<String, String>> =
+        std::collections::HashMap::new();
+    let mut current = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(section.to_string());
+            sections.entry(section.to_string()).or_default();
+        } else if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+    sections
+}
+
+/// `app_icon` and `image-path` may be a `file://` URI or an absolute path instead of an icon name
+pub fn path_from_icon_value(value: &str) -> Option<std::path::PathBuf> {
+    let path = if let Some(uri) = value.strip_prefix("file://") {
+        // file://localhost/path and file:///path are both valid
+        let uri = uri.strip_prefix("localhost").unwrap_or(uri);
+        std::path::PathBuf::from(percent_decode(uri)?)
+    } else if value.starts_with('/') {
+        std::path::PathBuf::from(value)
+    } else {
+        return None;
+    };
+    path.is_file().then_some(path)
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::default();
+            hasher.write(bytes);
+            hasher.0
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn cache_with_another_header_is_stale() {
+        let content = "iwwc-icons 1 0123456789abcdef\n48\tfirefox\t/usr/share/icons/firefox.png\n";
+        assert!(parse_cache(content, "iwwc-icons 2 0123456789abcdef").is_none());
+        let memo = parse_cache(content, "iwwc-icons 1 0123456789abcdef").unwrap();
+        assert_eq!(
+            memo.get(&("firefox".to_string(), 48)),
+            Some(&Some(std::path::PathBuf::from(
+                "/usr/share/icons/firefox.png"
+            )))
+        );
+    }
+
+    #[test]
+    fn cache_is_rewritten_without_duplicates() {
+        let dir = TempDir::new("icons-cache");
+        let cache = dir.write(
+            "icons.cache",
+            "header\n48\tapp\t/old/app.png\n48\tapp\t/new/app.png\n48\tgone\t\n48\tgone\t\n",
+        );
+        let memo = parse_cache(&std::fs::read_to_string(&cache).unwrap(), "header").unwrap();
+        drop(open_cache(&cache, "header", &memo).unwrap());
+
+        let content = std::fs::read_to_string(&cache).unwrap();
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines[1..].sort_unstable();
+        assert_eq!(lines, ["header", "48\tapp\t/new/app.png", "48\tgone\t"]);
+        assert_eq!(parse_cache(&content, "header"), Some(memo));
+    }
+
+    /// Removed again when dropped
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("iwwc-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, path: &str, content: &str) -> std::path::PathBuf {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn directory(kind: DirectoryType, size: u32, min_size: u32, max_size: u32) -> ThemeDirectory {
+        ThemeDirectory {
+            path: String::new(),
+            size,
+            min_size,
+            max_size,
+            threshold: 2,
+            scale: 1,
+            kind,
+        }
+    }
+
+    #[test]
+    fn index_theme_directory_types() {
+        let dir = TempDir::new("icons-index");
+        dir.write(
+            "test/index.theme",
+            "[Icon Theme]\n\
+             Name=Test\n\
+             Directories=16x16/apps,scalable/apps,32x32/apps,missing\n\
+             ScaledDirectories=16x16@2/apps\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\
+             [scalable/apps]\nSize=48\nMinSize=8\nMaxSize=512\nType=Scalable\n\
+             [32x32/apps]\nSize=32\nThreshold=4\n\
+             [16x16@2/apps]\nSize=16\nScale=2\nType=Fixed\n",
+        );
+        let theme = IconTheme::load("test", std::slice::from_ref(&dir.0)).unwrap();
+        let kinds: Vec<(&str, DirectoryType)> = theme
+            .directories
+            .iter()
+            .map(|directory| (directory.path.as_str(), directory.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("16x16/apps", DirectoryType::Fixed),
+                ("scalable/apps", DirectoryType::Scalable),
+                ("32x32/apps", DirectoryType::Threshold),
+                ("16x16@2/apps", DirectoryType::Fixed),
+            ]
+        );
+
+        let [fixed, scalable, threshold, scaled] = &theme.directories[..] else {
+            unreachable!()
+        };
+        assert!(fixed.matches_size(16, 1) && !fixed.matches_size(17, 1));
+        assert!(scalable.matches_size(8, 1) && scalable.matches_size(512, 1));
+        assert!(!scalable.matches_size(513, 1));
+        assert!(threshold.matches_size(28, 1) && threshold.matches_size(36, 1));
+        assert!(!threshold.matches_size(27, 1) && !threshold.matches_size(37, 1));
+        assert!(scaled.matches_size(16, 2) && !scaled.matches_size(16, 1));
+    }
+
+    #[test]
+    fn size_distance_is_zero_inside_the_range() {
+        let fixed = directory(DirectoryType::Fixed, 48, 48, 48);
+        assert_eq!(fixed.size_distance(48, 1), 0);
+        assert_eq!(fixed.size_distance(32, 1), 16);
+        assert_eq!(fixed.size_distance(64, 1), 16);
+
+        let scalable = directory(DirectoryType::Scalable, 48, 16, 256);
+        assert_eq!(scalable.size_distance(100, 1), 0);
+        assert_eq!(scalable.size_distance(8, 1), 8);
+        assert_eq!(scalable.size_distance(300, 1), 44);
+
+        // a threshold larger than the size must not underflow
+        let mut threshold = directory(DirectoryType::Threshold, 2, 2, 2);
+        threshold.threshold = 4;
+        assert_eq!(threshold.size_distance(1, 1), 0);
+        assert_eq!(threshold.size_distance(10, 1), 4);
+    }
+
+    #[test]
+    fn closest_size_is_selected() {
+        let dir = TempDir::new("icons-size");
+        dir.write(
+            "test/index.theme",
+            "[Icon Theme]\nDirectories=16x16/apps,48x48/apps,scalable/apps\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n\
+             [scalable/apps]\nSize=64\nMinSize=8\nMaxSize=256\nType=Scalable\n",
+        );
+        let small = dir.write("test/16x16/apps/app.png", "");
+        let large = dir.write("test/48x48/apps/app.png", "");
+        let scalable = dir.write("test/scalable/apps/app.svg", "");
+        let only_fixed = dir.write("test/48x48/apps/fixed.png", "");
+        dir.write("test/16x16/apps/fixed.png", "");
+        let lookup = IconLookup::new(
+            "test".to_string(),
+            vec![dir.0.clone()],
+            dir.0.join("pixmaps"),
+        );
+
+        assert_eq!(lookup.find_icon("app", 16), Some(small));
+        assert_eq!(lookup.find_icon("app", 48), Some(large));
+        assert_eq!(lookup.find_icon("app", 100), Some(scalable.clone()));
+        assert_eq!(lookup.find_icon("app", 300), Some(scalable));
+        assert_eq!(lookup.find_icon("fixed", 40), Some(only_fixed));
+        assert_eq!(lookup.find_icon("missing", 48), None);
+    }
+
+    #[test]
+    fn inherited_themes_come_in_order_with_hicolor_last() {
+        let dir = TempDir::new("icons-inherits");
+        let index = |inherits: &str| {
+            format!(
+                "[Icon Theme]\nInherits={inherits}\nDirectories=apps\n[apps]\nSize=48\nType=Fixed\n"
+            )
+        };
+        dir.write("child/index.theme", &index("first,second"));
+        dir.write("first/index.theme", &index("hicolor,grandparent"));
+        dir.write("second/index.theme", &index(""));
+        dir.write("grandparent/index.theme", &index(""));
+        dir.write("hicolor/index.theme", &index(""));
+        let from_first = dir.write("first/apps/shared.png", "");
+        dir.write("second/apps/shared.png", "");
+        let from_second = dir.write("second/apps/late.png", "");
+        dir.write("hicolor/apps/late.png", "");
+        let from_hicolor = dir.write("hicolor/apps/fallback.png", "");
+        let lookup = IconLookup::new(
+            "child".to_string(),
+            vec![dir.0.clone()],
+            dir.0.join("pixmaps"),
+        );
+
+        let names: Vec<&str> = lookup
+            .themes
+            .iter()
+            .map(|theme| theme.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["child", "first", "grandparent", "second", "hicolor"]
+        );
+        assert_eq!(lookup.find_icon("shared", 48), Some(from_first));
+        assert_eq!(lookup.find_icon("late", 48), Some(from_second));
+        assert_eq!(lookup.find_icon("fallback", 48), Some(from_hicolor));
+    }
+
+    #[test]
+    fn unthemed_icons_fall_back_to_pixmaps() {
+        let dir = TempDir::new("icons-pixmaps");
+        dir.write(
+            "hicolor/index.theme",
+            "[Icon Theme]\nDirectories=apps\n[apps]\nSize=48\nType=Fixed\n",
+        );
+        let unthemed = dir.write("icons/both.png", "");
+        dir.write("pixmaps/both.png", "");
+        let pixmap = dir.write("pixmaps/legacy.xpm", "");
+        let lookup = IconLookup::new(
+            "missing-theme".to_string(),
+            vec![dir.0.join("icons"), dir.0.clone()],
+            dir.0.join("pixmaps"),
+        );
+
+        assert_eq!(lookup.themes.len(), 1);
+        assert_eq!(lookup.find_icon("both", 48), Some(unthemed));
+        assert_eq!(lookup.find_icon("legacy", 48), Some(pixmap));
+        assert_eq!(lookup.find_icon("pixmaps/legacy", 48), None);
     }
-    if icons.is_empty() { None } else { Some(icons) }
 }
@@ -0,0 +1,408 @@
+/// A run of body text sharing the same style
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Style {
+    bold: u32,
+    italic: u32,
+    underline: u32,
+    link: Option<String>,
+}
+
+/// Parses the `body-markup` subset of the spec: `<b>`, `<i>`, `<u>`, `<a href>`, `<img alt>`
+/// and entities. Unknown or stray tags are dropped, a `<` or `&` that does not start a tag or
+/// an entity is kept as text.
+pub fn parse(body: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Style::default();
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some((tag, len)) = parse_tag(rest) {
+                rest = &rest[len..];
+                match (tag.name.as_str(), tag.closing) {
+                    ("b", false) => style.bold += 1,
+                    ("b", true) => style.bold = style.bold.saturating_sub(1),
+                    ("i", false) => style.italic += 1,
+                    ("i", true) => style.italic = style.italic.saturating_sub(1),
+                    ("u", false) => style.underline += 1,
+                    ("u", true) => style.underline = style.underline.saturating_sub(1),
+                    ("a", false) => style.link = tag.attribute("href").map(decode_entities),
+                    ("a", true) => style.link = None,
+                    ("img", false) => {
+                        // images are not rendered inline, the alt text stands in for them
+                        if let Some(alt) = tag.attribute("alt").filter(|alt| !alt.is_empty()) {
+                            push(&mut spans, &style, &decode_entities(alt));
+                        }
+                    }
+                    ("br", _) => push(&mut spans, &style, "\n"),
+                    _ => {}
+                }
+                continue;
+            }
+        } else if c == '&' {
+            if let Some((decoded, len)) = parse_entity(rest) {
+                push(&mut spans, &style, decoded.encode_utf8(&mut [0; 4]));
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        push(&mut spans, &style, c.encode_utf8(&mut [0; 4]));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    spans
+}
+
+/// The body without any markup, for the clipboard and logs
+pub fn plain_text(body: &str) -> String {
+    parse(body).into_iter().map(|span| span.text).collect()
+}
+
+/// `text` as markup that [`parse`] turns back into exactly `text`
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether a link from a body may be opened, only `http`, `https` and `mailto` are
+pub fn is_allowed_link(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        ["http", "https", "mailto"]
+            .iter()
+            .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    })
+}
+
+fn push(spans: &mut Vec<Span>, style: &Style, text: &str) {
+    let (bold, italic, underline) = (style.bold > 0, style.italic > 0, style.underline > 0);
+    match spans.last_mut() {
+        Some(last)
+            if last.bold == bold
+                && last.italic == italic
+                && last.underline == underline
+                && last.link == style.link =>
+        {
+            last.text.push_str(text);
+        }
+        _ => spans.push(Span {
+            text: text.to_string(),
+            bold,
+            italic,
+            underline,
+            link: style.link.clone(),
+        }),
+    }
+}
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, &'a str)>,
+}
+
+impl Tag<'_> {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// `<name attr="value" ...>`, `</name>` or `<name/>` at the start of `input`, with its length
+fn parse_tag(input: &str) -> Option<(Tag<'_>, usize)> {
+    let mut position = 1;
+    let closing = input[position..].starts_with('/');
+    if closing {
+        position += 1;
+    }
+    let name_len = input[position..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(input.len() - position);
+    let name = &input[position..position + name_len];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    position += name_len;
+
+    let mut attributes = Vec::new();
+    loop {
+        let rest = &input[position..];
+        let trimmed = rest.trim_start();
+        position += rest.len() - trimmed.len();
+        let rest = &input[position..];
+
+        if let Some(after) = rest.strip_prefix("/>") {
+            position = input.len() - after.len();
+            break;
+        }
+        if rest.starts_with('>') {
+            position += 1;
+            break;
+        }
+        // end of input or a new tag before this one was closed, not a tag after all
+        if rest.is_empty() || rest.starts_with('<') {
+            return None;
+        }
+
+        let key_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/' || c == '<')
+            .unwrap_or(rest.len());
+        if key_len == 0 {
+            // a stray character such as `/` in the middle of the tag
+            position += rest.chars().next()?.len_utf8();
+            continue;
+        }
+        let key = rest[..key_len].to_lowercase();
+        position += key_len;
+
+        let rest = &input[position..];
+        let Some(value_start) = rest.trim_start().strip_prefix('=') else {
+            attributes.push((key, ""));
+            continue;
+        };
+        let value_start = value_start.trim_start();
+        position = input.len() - value_start.len();
+        let value = match value_start.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = value_start[1..].find(quote)?;
+                position += end + 2;
+                &value_start[1..end + 1]
+            }
+            _ => {
+                let end = value_start
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(value_start.len());
+                position += end;
+                &value_start[..end]
+            }
+        };
+        attributes.push((key, value));
+    }
+
+    Some((
+        Tag {
+            name: name.to_lowercase(),
+            closing,
+            attributes,
+        },
+        position,
+    ))
+}
+
+/// `&name;`, `&#decimal;` or `&#xhex;` at the start of `input`, with its length
+fn parse_entity(input: &str) -> Option<(char, usize)> {
+    // the longest entity is `&#x10ffff;`, bounded by chars so a multibyte character right
+    // after the `&` can not make every `&` scan the rest of the input
+    let end = input
+        .char_indices()
+        .take(12)
+        .find(|(_, c)| *c == ';')
+        .map(|(index, _)| index)?;
+    let entity = &input[1..end];
+    let decoded = match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+    };
+    Some((decoded, end + 1))
+}
+
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            if let Some((entity, len)) = parse_entity(rest) {
+                decoded.push(entity);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        decoded.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str) -> Span {
+        Span {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plain_body_is_one_span() {
+        assert_eq!(parse("hello world"), [span("hello world")]);
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn nested_tags() {
+        assert_eq!(
+            parse("a<b>b<i>c</i></b><u>d</u>"),
+            [
+                span("a"),
+                Span {
+                    bold: true,
+                    ..span("b")
+                },
+                Span {
+                    bold: true,
+                    italic: true,
+                    ..span("c")
+                },
+                Span {
+                    underline: true,
+                    ..span("d")
+                },
+            ]
+        );
+        // the same tag twice only ends with its last closing tag
+        assert_eq!(
+            parse("<b><b>a</b>b</b>c"),
+            [
+                Span {
+                    bold: true,
+                    ..span("ab")
+                },
+                span("c")
+            ]
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            parse("<a href=\"https://example.com/?a=1&amp;b=2\">site</a> end"),
+            [
+                Span {
+                    link: Some("https://example.com/?a=1&b=2".to_string()),
+                    ..span("site")
+                },
+                span(" end")
+            ]
+        );
+        assert_eq!(parse("<a href=x>y</a>")[0].link.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn unknown_and_stray_tags_are_dropped() {
+        assert_eq!(parse("<p>a</p><font color=red>b</font></b>"), [span("ab")]);
+        assert_eq!(parse("<img src=x alt=\"[pic]\"/>"), [span("[pic]")]);
+        assert_eq!(parse("a<br>b"), [span("a\nb")]);
+    }
+
+    #[test]
+    fn unclosed_tags() {
+        // a `<` that never becomes a tag is text
+        assert_eq!(parse("1 < 2"), [span("1 < 2")]);
+        assert_eq!(parse("a <b"), [span("a <b")]);
+        assert_eq!(parse("<a href=\"x"), [span("<a href=\"x")]);
+        assert_eq!(
+            parse("<b <i>x"),
+            [
+                span("<b "),
+                Span {
+                    italic: true,
+                    ..span("x")
+                }
+            ]
+        );
+        // an opened tag that is never closed lasts until the end
+        assert_eq!(
+            parse("<b>x"),
+            [Span {
+                bold: true,
+                ..span("x")
+            }]
+        );
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            parse("&lt;&gt;&amp;&quot;&apos;&nbsp;&#65;&#x42;"),
+            [span("<>&\"'\u{a0}AB")]
+        );
+        // unknown, unterminated and invalid entities stay as they are
+        assert_eq!(
+            parse("&foo; & &amp &#xd800;"),
+            [span("&foo; & &amp &#xd800;")]
+        );
+    }
+
+    #[test]
+    fn entity_search_is_bounded_by_chars() {
+        assert_eq!(parse_entity("&#x10ffff;"), Some(('\u{10ffff}', 10)));
+        // multibyte characters neither break nor widen the search
+        assert_eq!(parse_entity("&é;"), None);
+        assert_eq!(parse_entity("&éééééééééé;"), None);
+        assert_eq!(parse_entity("&aaaaaaaaaaaaaaaa;"), None);
+        let body = "&é".repeat(10_000) + ";";
+        assert_eq!(plain_text(&body), body);
+    }
+
+    #[test]
+    fn plain_text_drops_markup() {
+        assert_eq!(
+            plain_text("<b>Build</b> &amp; <a href=\"https://ci\">test</a> <i>done"),
+            "Build & test done"
+        );
+    }
+
+    #[test]
+    fn escaped_text_stays_plain() {
+        let text = "<b>not bold</b> & <a href=\"x\">no link</a> &amp;";
+        assert_eq!(
+            escape(text),
+            "&lt;b&gt;not bold&lt;/b&gt; &amp; &lt;a href=\"x\"&gt;no link&lt;/a&gt; &amp;amp;"
+        );
+        assert_eq!(parse(&escape(text)), [span(text)]);
+    }
+
+    #[test]
+    fn allowed_links() {
+        assert!(is_allowed_link("https://example.com"));
+        assert!(is_allowed_link("HTTP://example.com"));
+        assert!(is_allowed_link("mailto:someone@example.com"));
+        assert!(!is_allowed_link("file:///etc/passwd"));
+        assert!(!is_allowed_link("javascript:alert(1)"));
+        assert!(!is_allowed_link("--help"));
+        assert!(!is_allowed_link("example.com"));
+        assert!(!is_allowed_link(""));
+    }
+}
@@ -1,4 +1,10 @@
 pub mod config;
+pub mod desktop_entry;
+pub mod history;
 pub mod icons;
+pub mod markup;
 pub mod notification;
+pub mod rules;
 pub mod shared;
+pub mod validation;
+pub mod xpm;
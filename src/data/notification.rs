@@ -8,15 +8,162 @@ pub struct Notification {
     pub actions: Vec<String>,
     pub expire_timeout: i32,
     pub notification_id: u32,
-    pub desktop_entry: String,
+    pub hints: Hints,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Urgency {
+    Low = 0,
+    #[default]
+    Normal = 1,
+    Critical = 2,
+}
+
+impl Urgency {
+    /// The name used in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// Raw pixels from the `image-data` hint, `(iiibiiay)` on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub width: i32,
+    pub height: i32,
+    pub rowstride: i32,
+    pub has_alpha: bool,
+    pub bits_per_sample: i32,
+    pub channels: i32,
+    pub data: Vec<u8>,
+}
+
+impl ImageData {
+    /// Repacks the buffer into tightly packed RGBA8, dropping the rowstride padding.
+    /// Returns `None` for formats the spec does not allow or truncated buffers.
+    pub fn to_rgba(&self) -> Option<(u32, u32, Vec<u8>)> {
+        let expected_channels = if self.has_alpha { 4 } else { 3 };
+        if self.width <= 0
+            || self.height <= 0
+            || self.bits_per_sample != 8
+            || self.channels != expected_channels
+        {
+            return None;
+        }
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let channels = self.channels as usize;
+        let rowstride = usize::try_from(self.rowstride).ok()?;
+        let row_length = width * channels;
+        // the last row is allowed to be unpadded
+        let length = rowstride
+            .checked_mul(height - 1)
+            .and_then(|length| length.checked_add(row_length))?;
+        if rowstride < row_length || self.data.len() < length {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in self.data.chunks(rowstride).take(height) {
+            for pixel in row[..row_length].chunks_exact(channels) {
+                pixels.extend_from_slice(&pixel[..3]);
+                pixels.push(if self.has_alpha { pixel[3] } else { u8::MAX });
+            }
+        }
+        Some((width as u32, height as u32, pixels))
+    }
+}
+
+/// Typed view of the hints dictionary, unknown or malformed hints are left at their defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hints {
+    pub urgency: Urgency,
+    pub category: Option<String>,
+    pub desktop_entry: Option<String>,
+    pub image_data: Option<ImageData>,
+    pub image_path: Option<String>,
+    pub sound_file: Option<String>,
+    pub sound_name: Option<String>,
+    pub suppress_sound: bool,
+    pub transient: bool,
+    pub resident: bool,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub action_icons: bool,
+    pub value: Option<i32>,
+}
+
+impl Notification {
+    /// `actions` comes as a flat `[key, label, key, label, ...]` list, the "default" key is
+    /// left out as it is bound to clicking the notification itself
+    pub fn action_buttons(&self) -> Vec<(String, String)> {
+        self.actions
+            .chunks_exact(2)
+            .filter(|pair| pair[0] != "default")
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect()
+    }
+
+    pub fn has_default_action(&self) -> bool {
+        self.actions
+            .chunks_exact(2)
+            .any(|pair| pair[0] == "default")
+    }
+}
+
+/// Reason codes of the NotificationClosed signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    CloseNotification = 3,
+    Undefined = 4,
+}
+
+impl CloseReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Expired => "expired",
+            Self::Dismissed => "dismissed",
+            Self::CloseNotification => "close-notification",
+            Self::Undefined => "undefined",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum NotificationAction {
-    ActionClose { notification_id: u32, reason: u32 },
-    ActionInvoked { notification_id: u32 },
-    Notify { notification: Notification },
-    Close { notification_id: u32 },
+    ActionClose {
+        notification_id: u32,
+        reason: CloseReason,
+    },
+    ActionInvoked {
+        notification_id: u32,
+        action_key: String,
+    },
+    Notify {
+        notification: Notification,
+    },
+    Close {
+        notification_id: u32,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,6 +175,8 @@ pub struct PreCalc {
     pub text_summary_paddings: iced::Padding,
     pub text_body_paddings: iced::Padding,
     pub text_paddings_block: iced::Padding,
+    pub actions_height: u32,
+    pub font_size_action: f32,
 }
 
 impl PreCalc {
@@ -71,6 +220,76 @@ impl PreCalc {
                 left: config.notifications.height as f32 * 0.15,
                 right: 0.0,
             },
+            actions_height: (config.notifications.height as f32 * 0.4) as u32,
+            font_size_action: config.notifications.height as f32 * 0.15,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: i32, height: i32, rowstride: i32, has_alpha: bool, data: Vec<u8>) -> ImageData {
+        ImageData {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            bits_per_sample: 8,
+            channels: if has_alpha { 4 } else { 3 },
+            data,
         }
     }
+
+    #[test]
+    fn rowstride_padding_is_dropped() {
+        // 1x2 RGB with two bytes of padding after the first row, the last row is unpadded
+        let data = vec![1, 2, 3, 0xaa, 0xbb, 4, 5, 6];
+        assert_eq!(
+            image(1, 2, 5, false, data).to_rgba(),
+            Some((1, 2, vec![1, 2, 3, 255, 4, 5, 6, 255]))
+        );
+    }
+
+    #[test]
+    fn alpha_is_kept_or_filled_in() {
+        let rgba = image(2, 1, 8, true, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(rgba.to_rgba(), Some((2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8])));
+        let rgb = image(2, 1, 6, false, vec![1, 2, 3, 5, 6, 7]);
+        assert_eq!(
+            rgb.to_rgba(),
+            Some((2, 1, vec![1, 2, 3, 255, 5, 6, 7, 255]))
+        );
+    }
+
+    #[test]
+    fn unsupported_formats_are_rejected() {
+        let mut sixteen_bits = image(1, 1, 6, false, vec![0; 6]);
+        sixteen_bits.bits_per_sample = 16;
+        assert_eq!(sixteen_bits.to_rgba(), None);
+        let mut wrong_channels = image(1, 1, 4, false, vec![0; 4]);
+        wrong_channels.channels = 4;
+        assert_eq!(wrong_channels.to_rgba(), None);
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        assert_eq!(image(2, 2, 8, false, vec![0; 13]).to_rgba(), None);
+        assert!(image(2, 2, 8, false, vec![0; 14]).to_rgba().is_some());
+        // a rowstride shorter than a row would read pixels twice
+        assert_eq!(image(2, 2, 5, false, vec![0; 12]).to_rgba(), None);
+    }
+
+    #[test]
+    fn degenerate_sizes_do_not_panic() {
+        assert_eq!(image(0, 1, 0, false, Vec::new()).to_rgba(), None);
+        assert_eq!(image(1, 0, 3, false, vec![0; 3]).to_rgba(), None);
+        assert_eq!(image(-1, 1, 3, false, vec![0; 3]).to_rgba(), None);
+        assert_eq!(image(1, 2, -3, false, vec![0; 6]).to_rgba(), None);
+        assert_eq!(
+            image(i32::MAX, i32::MAX, i32::MAX, true, vec![0; 16]).to_rgba(),
+            None
+        );
+    }
 }
@@ -0,0 +1,161 @@
+/// What the `[[rules]]` matching a notification do to it, merged in config order so later
+/// rules override earlier ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleOutcome {
+    pub drop: bool,
+    pub mute: bool,
+    pub expire_timeout: Option<f64>,
+    pub urgency: Option<crate::data::notification::Urgency>,
+    pub icon: Option<String>,
+    pub border_color: Option<iced::Color>,
+    pub background_color: Option<iced::Color>,
+    pub primary_text_color: Option<iced::Color>,
+    pub secondary_text_color: Option<iced::Color>,
+    pub location: Option<iced_layershell::reexport::Anchor>,
+    /// every matching rule runs its command, nothing runs for a dropped notification
+    pub exec: Vec<Vec<String>>,
+}
+
+impl RuleOutcome {
+    /// `style` with the colors the rules set
+    pub fn restyle(
+        &self,
+        style: crate::data::config::NotificationStyle,
+    ) -> crate::data::config::NotificationStyle {
+        crate::data::config::NotificationStyle {
+            border_color: self.border_color.unwrap_or(style.border_color),
+            background_color: self.background_color.unwrap_or(style.background_color),
+            primary_text_color: self.primary_text_color.unwrap_or(style.primary_text_color),
+            secondary_text_color: self
+                .secondary_text_color
+                .unwrap_or(style.secondary_text_color),
+        }
+    }
+}
+
+pub fn evaluate(
+    rules: &[crate::data::config::Rule],
+    notification: &crate::data::notification::Notification,
+) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+    for rule in rules.iter().filter(|rule| rule.matches(notification)) {
+        outcome.drop |= rule.drop;
+        outcome.mute |= rule.mute;
+        outcome.expire_timeout = rule.expire_timeout.or(outcome.expire_timeout);
+        outcome.urgency = rule.set_urgency.or(outcome.urgency);
+        outcome.icon = rule.icon.clone().or(outcome.icon);
+        outcome.border_color = rule.border_color.or(outcome.border_color);
+        outcome.background_color = rule.background_color.or(outcome.background_color);
+        outcome.primary_text_color = rule.primary_text_color.or(outcome.primary_text_color);
+        outcome.secondary_text_color = rule.secondary_text_color.or(outcome.secondary_text_color);
+        outcome.location = rule.location.or(outcome.location);
+        if !rule.exec.is_empty() {
+            outcome.exec.push(rule.exec.clone());
+        }
+    }
+    if outcome.drop {
+        outcome.exec.clear();
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::notification::Urgency;
+
+    fn rules(content: &str) -> Vec<crate::data::config::Rule> {
+        toml::from_str::<crate::data::config::Config>(content)
+            .unwrap()
+            .rules
+    }
+
+    fn notification(
+        app_name: &str,
+        summary: &str,
+        body: &str,
+    ) -> crate::data::notification::Notification {
+        crate::data::notification::Notification {
+            app_name: app_name.to_string(),
+            app_icon: String::new(),
+            replaces_id: 0,
+            summary: summary.to_string(),
+            body: body.to_string(),
+            actions: Vec::new(),
+            expire_timeout: -1,
+            notification_id: 1,
+            hints: Default::default(),
+        }
+    }
+
+    #[test]
+    fn conditions_must_all_match() {
+        let rule = &rules(
+            "[[rules]]\napp_name = \"slack\"\nsummary = \"(?i)urgent\"\n\
+             urgency = \"normal\"\nmute = true",
+        )[0];
+        assert!(rule.matches(&notification("Slack", "URGENT: deploy", "")));
+        assert!(!rule.matches(&notification("Slack", "lunch", "")));
+        assert!(!rule.matches(&notification("Discord", "urgent", "")));
+        let mut critical = notification("Slack", "urgent", "");
+        critical.hints.urgency = Urgency::Critical;
+        assert!(!rule.matches(&critical));
+    }
+
+    #[test]
+    fn hints_a_notification_lacks_never_match() {
+        let rule = &rules("[[rules]]\ncategory = \"email.arrived\"\nmute = true")[0];
+        assert!(!rule.matches(&notification("Thunderbird", "", "")));
+        let mut email = notification("Thunderbird", "", "");
+        email.hints.category = Some("Email.Arrived".to_string());
+        assert!(rule.matches(&email));
+    }
+
+    #[test]
+    fn body_is_matched_without_markup() {
+        let rule = &rules("[[rules]]\nbody = \"^build failed$\"\ndrop = true")[0];
+        assert!(rule.matches(&notification("ci", "", "<b>build</b> failed")));
+        assert!(!rule.matches(&notification("ci", "", "build passed")));
+    }
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let rules = rules(
+            "[[rules]]\nexpire_timeout = 3\nicon = \"a\"\nexec = [\"one\"]\nmute = true\n\
+             [[rules]]\napp_name = \"other\"\ndrop = true\n\
+             [[rules]]\nexpire_timeout = 5\nset_urgency = \"low\"\nexec = [\"two\", \"x\"]",
+        );
+        let outcome = evaluate(&rules, &notification("app", "", ""));
+        assert_eq!(
+            outcome,
+            RuleOutcome {
+                mute: true,
+                expire_timeout: Some(5.0),
+                urgency: Some(Urgency::Low),
+                icon: Some("a".to_string()),
+                exec: vec![
+                    vec!["one".to_string()],
+                    vec!["two".to_string(), "x".to_string()]
+                ],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            evaluate(&[], &notification("app", "", "")),
+            RuleOutcome::default()
+        );
+    }
+
+    #[test]
+    fn dropped_notifications_run_no_commands() {
+        let rules = rules(
+            "[[rules]]\nexec = [\"notify-sound\"]\n\
+             [[rules]]\napp_name = \"spam\"\ndrop = true",
+        );
+        let outcome = evaluate(&rules, &notification("spam", "", ""));
+        assert!(outcome.drop);
+        assert!(outcome.exec.is_empty());
+        let outcome = evaluate(&rules, &notification("app", "", ""));
+        assert_eq!(outcome.exec, [vec!["notify-sound".to_string()]]);
+    }
+}
@@ -1,6 +1,31 @@
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
-use std::sync::Mutex;
 
-pub static ICONS: Lazy<Mutex<HashMap<String, std::path::PathBuf>>> =
-    Lazy::new(|| Mutex::new(crate::data::icons::get_system_icons_paths()));
+pub static ICONS: Lazy<crate::data::icons::IconResolver> =
+    Lazy::new(crate::data::icons::IconResolver::load);
+
+pub static DESKTOP_ENTRIES: Lazy<crate::data::desktop_entry::DesktopEntries> =
+    Lazy::new(crate::data::desktop_entry::DesktopEntries::load);
+
+/// Reported by `GetCapabilities`, kept in sync with the loaded config
+pub static CAPABILITIES: Lazy<std::sync::RwLock<Vec<String>>> =
+    Lazy::new(|| std::sync::RwLock::new(Vec::new()));
+
+pub fn set_capabilities(config: &crate::data::config::Config) {
+    match CAPABILITIES.write() {
+        Ok(mut capabilities) => {
+            *capabilities = config.notifications.capabilities(config.history.enable)
+        }
+        Err(e) => log::error!("Failed to update the capabilities: {e}"),
+    }
+}
+
+/// Whether `capability` is reported, features that are removed from it are turned off too
+pub fn has_capability(capability: &str) -> bool {
+    match CAPABILITIES.read() {
+        Ok(capabilities) => capabilities.iter().any(|reported| reported == capability),
+        Err(e) => {
+            log::error!("Failed to read the capabilities: {e}");
+            false
+        }
+    }
+}
@@ -0,0 +1,901 @@
+use toml::de::{DeTable, DeValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {severity}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Parses and validates a config file. Unlike plain deserialization this keeps going after the
+/// first problem so every bad value gets reported at once. The config is only returned when
+/// there are no errors, warnings are returned either way.
+pub fn check(content: &str) -> (Option<crate::data::config::Config>, Vec<Diagnostic>) {
+    let mut checker = Checker {
+        content,
+        diagnostics: Vec::new(),
+        reported_spans: Vec::new(),
+        invalid_values: Vec::new(),
+    };
+
+    let (document, syntax_errors) = DeTable::parse_recoverable(content);
+    if !syntax_errors.is_empty() {
+        for error in syntax_errors {
+            checker.push(
+                Severity::Error,
+                error.span().unwrap_or(0..0),
+                error.message().to_string(),
+            );
+        }
+        return (None, checker.finish());
+    }
+    let document = document.into_inner();
+
+    checker.check_values(&document, &[]);
+
+    let config = match toml::from_str::<crate::data::config::Config>(content) {
+        Ok(config) => config,
+        Err(error) => {
+            let span = error.span().unwrap_or(0..0);
+            if !checker.reported_spans.contains(&span) {
+                checker.push(Severity::Error, span, error.message().to_string());
+                return (None, checker.finish());
+            }
+            // string encoded values were already reported above with a better message,
+            // drop them so the remaining checks still get a chance to run
+            match without_values(content, &checker.invalid_values) {
+                Some(config) => config,
+                None => return (None, checker.finish()),
+            }
+        }
+    };
+
+    checker.check_notifications(&document, &config.notifications);
+    checker.check_history(&document, &config.history);
+    checker.check_center(&document, &config.center);
+    checker.check_dnd(&document, &config.dnd);
+    checker.check_rules(&document, &config.rules);
+    checker.check_hooks(&document, &config.hooks);
+    checker.check_widgets(&document, &config.widgets);
+
+    if checker
+        .diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        (None, checker.finish())
+    } else {
+        (Some(config), checker.finish())
+    }
+}
+
+struct Checker<'a> {
+    content: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    reported_spans: Vec<std::ops::Range<usize>>,
+    invalid_values: Vec<Vec<String>>,
+}
+
+impl Checker<'_> {
+    fn finish(mut self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        self.diagnostics
+    }
+
+    fn push(&mut self, severity: Severity, span: std::ops::Range<usize>, message: String) {
+        let (line, column) = line_column(self.content, span.start);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line,
+            column,
+            message,
+        });
+        self.reported_spans.push(span);
+    }
+
+    /// Colors, anchors and layers are written as strings, so check every one of them by table path
+    /// instead of stopping at the first one serde complains about
+    fn check_values(&mut self, table: &DeTable<'_>, path: &[String]) {
+        for (key, value) in table {
+            let span = value.span();
+            let mut path = path.to_vec();
+            path.push(key.get_ref().to_string());
+            match value.get_ref() {
+                DeValue::String(string) => {
+                    if let Some(message) = check_string(&path, string) {
+                        self.push(
+                            Severity::Error,
+                            span,
+                            format!("{}: {message}", key.get_ref()),
+                        );
+                        self.invalid_values.push(path);
+                    }
+                }
+                DeValue::Table(table) => self.check_values(table, &path),
+                DeValue::Array(array) => {
+                    for (index, item) in array.iter().enumerate() {
+                        if let DeValue::Table(table) = item.get_ref() {
+                            let mut path = path.clone();
+                            path.push(index.to_string());
+                            self.check_values(table, &path);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_notifications(
+        &mut self,
+        document: &DeTable<'_>,
+        notifications: &crate::data::config::NotificationConfig,
+    ) {
+        use iced_layershell::reexport::Anchor;
+
+        let section = document
+            .get("notifications")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => Some(table),
+                _ => None,
+            });
+        let span = |key: &str| {
+            section
+                .and_then(|table| table.get(key))
+                .map_or(0..0, |value| value.span())
+        };
+
+        if notifications.height < 65 {
+            self.push(
+                Severity::Error,
+                span("height"),
+                format!(
+                    "notifications.height must be at least 65, got {}",
+                    notifications.height
+                ),
+            );
+        }
+        if notifications.width < 300 {
+            self.push(
+                Severity::Error,
+                span("width"),
+                format!(
+                    "notifications.width must be at least 300, got {}",
+                    notifications.width
+                ),
+            );
+        }
+        if !valid_timeout(notifications.local_expire_timeout) {
+            self.push(
+                Severity::Error,
+                span("local_expire_timeout"),
+                "notifications.local_expire_timeout must be a non-negative number of seconds, \
+                 use 0 to never expire"
+                    .to_string(),
+            );
+        }
+        if !valid_timeout(notifications.hover_resume_timeout) {
+            self.push(
+                Severity::Error,
+                span("hover_resume_timeout"),
+                "notifications.hover_resume_timeout must be a non-negative number of seconds"
+                    .to_string(),
+            );
+        }
+        for capability in notifications
+            .capabilities
+            .add
+            .iter()
+            .chain(&notifications.capabilities.remove)
+        {
+            if !KNOWN_CAPABILITIES.contains(&capability.as_str()) && !capability.starts_with("x-") {
+                let span = section
+                    .and_then(|table| table.get("capabilities"))
+                    .map_or(0..0, |value| value.span());
+                self.push(
+                    Severity::Warning,
+                    span,
+                    format!(
+                        "unknown capability \"{capability}\" in notifications.capabilities, \
+                         vendor extensions start with \"x-\""
+                    ),
+                );
+            }
+        }
+        for (name, urgency) in [
+            ("low", &notifications.low),
+            ("normal", &notifications.normal),
+            ("critical", &notifications.critical),
+        ] {
+            if urgency
+                .expire_timeout
+                .is_some_and(|timeout| !valid_timeout(timeout))
+            {
+                let span = section
+                    .and_then(|table| table.get(name))
+                    .and_then(|value| match value.get_ref() {
+                        DeValue::Table(table) => table.get("expire_timeout"),
+                        _ => None,
+                    })
+                    .map_or(0..0, |value| value.span());
+                self.push(
+                    Severity::Error,
+                    span,
+                    format!(
+                        "notifications.{name}.expire_timeout must be a non-negative number of \
+                         seconds, use 0 to never expire"
+                    ),
+                );
+            }
+        }
+        if notifications.max_notifications < 0 {
+            self.push(
+                Severity::Error,
+                span("max_notifications"),
+                "notifications.max_notifications can not be negative, use 0 for unlimited"
+                    .to_string(),
+            );
+        }
+        if notifications.border_width < 0.0 {
+            self.push(
+                Severity::Error,
+                span("border_width"),
+                "notifications.border_width can not be negative".to_string(),
+            );
+        }
+        for key in ["vertical_margin", "horizontal_margin"] {
+            let margin = if key == "vertical_margin" {
+                notifications.vertical_margin
+            } else {
+                notifications.horizontal_margin
+            };
+            if margin < 0 {
+                self.push(
+                    Severity::Warning,
+                    span(key),
+                    format!("notifications.{key} is negative, notifications may be off screen"),
+                );
+            }
+        }
+
+        let location = notifications.location;
+        if location.contains(Anchor::Top) && location.contains(Anchor::Bottom) {
+            self.push(
+                Severity::Error,
+                span("location"),
+                "notifications.location anchors both top and bottom, notifications need a \
+                 single edge to stack from"
+                    .to_string(),
+            );
+        } else if !location.contains(Anchor::Top) && !location.contains(Anchor::Bottom) {
+            self.push(
+                Severity::Warning,
+                span("location"),
+                "notifications.location is not anchored to top or bottom, margins and \
+                 stacking will be ignored"
+                    .to_string(),
+            );
+        }
+
+        // only meaningful once the sizes themselves are sane
+        if notifications.height >= 65 && notifications.width >= 300 {
+            let precalc =
+                crate::data::notification::PreCalc::generate(&crate::data::config::Config {
+                    notifications: notifications.clone(),
+                    ..Default::default()
+                });
+            if precalc.font_size_summary <= 0.0 || precalc.font_size_body <= 0.0 {
+                self.push(
+                    Severity::Error,
+                    span("width"),
+                    format!(
+                        "notifications.width {} is too small for height {}, text would not be \
+                         visible",
+                        notifications.width, notifications.height
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_history(
+        &mut self,
+        document: &DeTable<'_>,
+        history: &crate::data::config::HistoryConfig,
+    ) {
+        let section = document
+            .get("history")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => Some(table),
+                _ => None,
+            });
+        let span = |key: &str| {
+            section
+                .and_then(|table| table.get(key))
+                .map_or(0..0, |value| value.span())
+        };
+
+        if history.enable && history.max_entries == 0 {
+            self.push(
+                Severity::Error,
+                span("max_entries"),
+                "history.max_entries must be at least 1, set history.enable = false to keep no \
+                 history"
+                    .to_string(),
+            );
+        }
+        let per_app = section
+            .and_then(|table| table.get("per_app"))
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => Some(table),
+                _ => None,
+            });
+        for (app, limit) in &history.per_app {
+            if *limit > history.max_entries {
+                let span = per_app
+                    .and_then(|table| table.get(app.as_str()))
+                    .map_or_else(|| span("per_app"), |value| value.span());
+                self.push(
+                    Severity::Warning,
+                    span,
+                    format!(
+                        "history.per_app.{app} is above history.max_entries ({}), it has no effect",
+                        history.max_entries
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_center(&mut self, document: &DeTable<'_>, center: &crate::data::config::CenterConfig) {
+        use iced_layershell::reexport::Anchor;
+
+        let section = document
+            .get("center")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => Some(table),
+                _ => None,
+            });
+        // a size left at 0 is reported on the anchors that need to go with it
+        let span = |key: &str| {
+            section
+                .and_then(|table| table.get(key).or_else(|| table.get("location")))
+                .map_or(0..0, |value| value.span())
+        };
+
+        if center.width == 0
+            && !(center.location.contains(Anchor::Left) && center.location.contains(Anchor::Right))
+        {
+            self.push(
+                Severity::Error,
+                span("width"),
+                "center.width is 0 but the center is not anchored to both left and right"
+                    .to_string(),
+            );
+        }
+        if center.height == 0
+            && !(center.location.contains(Anchor::Top) && center.location.contains(Anchor::Bottom))
+        {
+            self.push(
+                Severity::Error,
+                span("height"),
+                "center.height is 0 but the center is not anchored to both top and bottom"
+                    .to_string(),
+            );
+        }
+        if center.margin < 0 {
+            self.push(
+                Severity::Warning,
+                span("margin"),
+                "center.margin is negative, the center may be off screen".to_string(),
+            );
+        }
+    }
+
+    fn check_dnd(&mut self, document: &DeTable<'_>, dnd: &crate::data::config::DndConfig) {
+        let items = document
+            .get("dnd")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => table.get("schedule"),
+                _ => None,
+            })
+            .and_then(|value| match value.get_ref() {
+                DeValue::Array(array) => Some(array),
+                _ => None,
+            });
+
+        for (index, period) in dnd.schedule.iter().enumerate() {
+            if period.from == period.to {
+                let span = items
+                    .and_then(|array| array.get(index))
+                    .map_or(0..0, |item| item.span());
+                self.push(
+                    Severity::Warning,
+                    span,
+                    format!(
+                        "dnd.schedule[{index}] starts and ends at the same time, it never applies"
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_rules(&mut self, document: &DeTable<'_>, rules: &[crate::data::config::Rule]) {
+        use iced_layershell::reexport::Anchor;
+
+        let items = document
+            .get("rules")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Array(array) => Some(array),
+                _ => None,
+            });
+
+        for (index, rule) in rules.iter().enumerate() {
+            let item = items.and_then(|array| array.get(index));
+            let span = |key: &str| {
+                item.and_then(|item| match item.get_ref() {
+                    DeValue::Table(table) => table.get(key).map(|value| value.span()),
+                    _ => None,
+                })
+                .or_else(|| item.map(|item| item.span()))
+                .unwrap_or(0..0)
+            };
+
+            let acts = rule.drop
+                || rule.mute
+                || rule.expire_timeout.is_some()
+                || rule.set_urgency.is_some()
+                || rule.icon.is_some()
+                || rule.border_color.is_some()
+                || rule.background_color.is_some()
+                || rule.primary_text_color.is_some()
+                || rule.secondary_text_color.is_some()
+                || rule.location.is_some()
+                || !rule.exec.is_empty();
+            if !acts {
+                self.push(
+                    Severity::Warning,
+                    item.map_or(0..0, |item| item.span()),
+                    format!("rules[{index}] has no actions, it does nothing"),
+                );
+            }
+            if rule
+                .expire_timeout
+                .is_some_and(|timeout| !valid_timeout(timeout))
+            {
+                self.push(
+                    Severity::Error,
+                    span("expire_timeout"),
+                    format!(
+                        "rules[{index}].expire_timeout must be a non-negative number of seconds, \
+                         use 0 to never expire"
+                    ),
+                );
+            }
+            if rule.location.is_some_and(|location| {
+                location.contains(Anchor::Top) && location.contains(Anchor::Bottom)
+            }) {
+                self.push(
+                    Severity::Error,
+                    span("location"),
+                    format!(
+                        "rules[{index}].location anchors both top and bottom, notifications need \
+                         a single edge to stack from"
+                    ),
+                );
+            }
+            if rule.exec.first().is_some_and(|program| program.is_empty()) {
+                self.push(
+                    Severity::Error,
+                    span("exec"),
+                    format!("rules[{index}].exec starts with an empty program name"),
+                );
+            }
+        }
+    }
+
+    fn check_hooks(&mut self, document: &DeTable<'_>, hooks: &crate::data::config::HooksConfig) {
+        let section = document
+            .get("hooks")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Table(table) => Some(table),
+                _ => None,
+            });
+        let span = |key: &str| {
+            section
+                .and_then(|table| table.get(key))
+                .map_or(0..0, |value| value.span())
+        };
+
+        for (key, command) in [
+            ("on_notify", &hooks.on_notify),
+            ("on_close", &hooks.on_close),
+            ("on_action", &hooks.on_action),
+        ] {
+            if command.first().is_some_and(|program| program.is_empty()) {
+                self.push(
+                    Severity::Error,
+                    span(key),
+                    format!("hooks.{key} starts with an empty program name"),
+                );
+            }
+        }
+        if hooks.timeout < 0.0 || !hooks.timeout.is_finite() {
+            self.push(
+                Severity::Error,
+                span("timeout"),
+                "hooks.timeout must be 0 or more seconds, 0 lets hooks run as long as they \
+                 need"
+                    .to_string(),
+            );
+        }
+    }
+
+    fn check_widgets(
+        &mut self,
+        document: &DeTable<'_>,
+        widgets: &[crate::data::config::WidgetWindow],
+    ) {
+        use iced_layershell::reexport::Anchor;
+
+        let items = document
+            .get("widgets")
+            .and_then(|value| match value.get_ref() {
+                DeValue::Array(array) => Some(array),
+                _ => None,
+            });
+
+        for (index, widget) in widgets.iter().enumerate() {
+            let item = items.and_then(|array| array.get(index));
+            let span = |key: &str| {
+                item.and_then(|item| match item.get_ref() {
+                    DeValue::Table(table) => table.get(key).map(|value| value.span()),
+                    _ => None,
+                })
+                .or_else(|| item.map(|item| item.span()))
+                .unwrap_or(0..0)
+            };
+            let name = if widget.name.is_empty() {
+                format!("widgets[{index}]")
+            } else {
+                format!("widget \"{}\"", widget.name)
+            };
+
+            if widget.width == 0
+                && !(widget.location.contains(Anchor::Left)
+                    && widget.location.contains(Anchor::Right))
+            {
+                self.push(
+                    Severity::Error,
+                    span("width"),
+                    format!("{name} has width 0 but is not anchored to both left and right"),
+                );
+            }
+            if widget.height == 0
+                && !(widget.location.contains(Anchor::Top)
+                    && widget.location.contains(Anchor::Bottom))
+            {
+                self.push(
+                    Severity::Error,
+                    span("height"),
+                    format!("{name} has height 0 but is not anchored to both top and bottom"),
+                );
+            }
+            if !widget.name.is_empty()
+                && widgets[..index]
+                    .iter()
+                    .any(|other| other.name == widget.name)
+            {
+                self.push(
+                    Severity::Error,
+                    span("name"),
+                    format!("{name} is defined more than once"),
+                );
+            }
+        }
+    }
+}
+
+fn without_values(content: &str, paths: &[Vec<String>]) -> Option<crate::data::config::Config> {
+    fn remove(value: &mut toml::Value, path: &[String]) {
+        let Some((last, parents)) = path.split_last() else {
+            return;
+        };
+        let mut current = value;
+        for segment in parents {
+            current = match current {
+                toml::Value::Table(table) => match table.get_mut(segment) {
+                    Some(value) => value,
+                    None => return,
+                },
+                toml::Value::Array(array) => {
+                    match segment.parse::<usize>().ok().and_then(|i| array.get_mut(i)) {
+                        Some(value) => value,
+                        None => return,
+                    }
+                }
+                _ => return,
+            };
+        }
+        if let toml::Value::Table(table) = current {
+            table.remove(last);
+        }
+    }
+
+    let mut document = toml::Value::Table(toml::from_str::<toml::Table>(content).ok()?);
+    for path in paths {
+        remove(&mut document, path);
+    }
+    document.try_into().ok()
+}
+
+/// Picks the check from the full table path, so a key only gets validated where the config
+/// actually reads it as a color, anchor, layer, time or regex
+fn check_string(path: &[String], value: &str) -> Option<String> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let is_color = |key: &str| {
+        matches!(
+            key,
+            "border_color" | "background_color" | "primary_text_color" | "secondary_text_color"
+        )
+    };
+    match path.as_slice() {
+        ["notifications", key]
+        | ["notifications", "low" | "normal" | "critical", key]
+        | ["rules", _, key]
+            if is_color(key) && crate::data::config::parse_color(value).is_none() =>
+        {
+            Some(format!(
+                "invalid color \"{value}\", expected \"#rgb\", \"#rgba\", \"#rrggbb\" or \
+                 \"#rrggbbaa\""
+            ))
+        }
+        ["notifications" | "center", "location"] | ["widgets" | "rules", _, "location"]
+            if crate::data::config::parse_anchor(value).is_none() =>
+        {
+            Some(format!(
+                "invalid anchor \"{value}\", expected a combination of top, bottom, left and right \
+                 or \"center\""
+            ))
+        }
+        ["dnd", "schedule", _, "from" | "to"]
+            if crate::data::config::parse_clock_time(value).is_none() =>
+        {
+            Some(format!("invalid time \"{value}\", expected \"HH:MM\""))
+        }
+        ["rules", _, "summary" | "body"] => crate::data::config::parse_regex(value).err(),
+        ["center", "layer"] | ["widgets", _, "layer"]
+            if crate::data::config::parse_layer(value).is_none() =>
+        {
+            Some(format!(
+                "invalid layer \"{value}\", expected background, bottom, top or overlay"
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(content.len());
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |newline| &before[newline + 1..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+/// Every capability of the Desktop Notifications Specification
+const KNOWN_CAPABILITIES: [&str; 10] = [
+    "action-icons",
+    "actions",
+    "body",
+    "body-hyperlinks",
+    "body-images",
+    "body-markup",
+    "icon-multi",
+    "icon-static",
+    "persistence",
+    "sound",
+];
+
+/// Negative, NaN and too large values can not be turned into a `Duration`
+fn valid_timeout(seconds: f64) -> bool {
+    std::time::Duration::try_from_secs_f64(seconds).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(content: &str) -> Vec<String> {
+        check(content)
+            .1
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn empty_config_is_valid() {
+        let (config, diagnostics) = check("");
+        assert!(config.is_some());
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn syntax_error_has_position() {
+        let (config, diagnostics) = check("[notifications]\nwidth = \n");
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, 2);
+    }
+
+    #[test]
+    fn every_invalid_string_is_reported() {
+        let content = "[notifications]\n\
+                       border_color = \"#12\"\n\
+                       location = \"middle\"\n\
+                       [center]\n\
+                       layer = \"sky\"\n";
+        let (config, diagnostics) = check(content);
+        assert!(config.is_none());
+        let positions: Vec<(usize, usize)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column))
+            .collect();
+        assert_eq!(positions, [(2, 16), (3, 12), (5, 9)]);
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("border_color: invalid color")
+        );
+        assert!(
+            diagnostics[1]
+                .message
+                .starts_with("location: invalid anchor")
+        );
+        assert!(diagnostics[2].message.starts_with("layer: invalid layer"));
+    }
+
+    #[test]
+    fn strings_are_checked_by_table_path() {
+        let path = |path: &[&str]| path.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        assert!(check_string(&path(&["rules", "0", "summary"]), "(").is_some());
+        assert!(check_string(&path(&["dnd", "schedule", "1", "to"]), "25:00").is_some());
+        assert!(
+            check_string(&path(&["notifications", "critical", "border_color"]), "red").is_some()
+        );
+        assert!(check_string(&path(&["widgets", "0", "layer"]), "sky").is_some());
+        assert!(check_string(&path(&["hooks", "summary"]), "(").is_none());
+        assert!(check_string(&path(&["rules", "0", "app_name"]), "(").is_none());
+        assert!(check_string(&path(&["dnd", "from"]), "25:00").is_none());
+        assert!(check_string(&path(&["dnd", "schedule", "0", "from"]), "22:30").is_none());
+    }
+
+    #[test]
+    fn color_errors_list_every_form() {
+        let messages = messages("[notifications]\nborder_color = \"#12\"\n");
+        assert_eq!(
+            messages,
+            [
+                "2:16: error: border_color: invalid color \"#12\", expected \"#rgb\", \"#rgba\", \
+              \"#rrggbb\" or \"#rrggbbaa\""
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_run_after_string_errors() {
+        let messages = messages("[notifications]\nbackground_color = \"red\"\nheight = 10\n");
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("2:20: error: background_color"));
+        assert_eq!(
+            messages[1],
+            "3:10: error: notifications.height must be at least 65, got 10"
+        );
+    }
+
+    #[test]
+    fn unknown_key_is_an_error() {
+        let (config, diagnostics) = check("[notifications]\nwidht = 400\n");
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+        assert!(diagnostics[0].message.contains("widht"));
+    }
+
+    #[test]
+    fn warnings_keep_the_config() {
+        let (config, diagnostics) = check("[notifications]\nlocation = \"left\"\n");
+        assert!(config.is_some());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 12));
+    }
+
+    #[test]
+    fn nested_tables_point_at_the_value() {
+        let messages = messages("[notifications.critical]\nexpire_timeout = -1\n");
+        assert_eq!(
+            messages,
+            [
+                "2:18: error: notifications.critical.expire_timeout must be a non-negative \
+              number of seconds, use 0 to never expire"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_reported() {
+        let (config, diagnostics) =
+            check("[[rules]]\napp_name = \"a\"\n\n[[rules]]\nexpire_timeout = -1\nexec = [\"\"]\n");
+        assert!(config.is_none());
+        let messages: Vec<(Severity, (usize, usize), &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.severity,
+                    (diagnostic.line, diagnostic.column),
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Warning,
+                    (1, 1),
+                    "rules[0] has no actions, it does nothing"
+                ),
+                (
+                    Severity::Error,
+                    (5, 18),
+                    "rules[1].expire_timeout must be a non-negative number of seconds, use 0 \
+                     to never expire"
+                ),
+                (
+                    Severity::Error,
+                    (6, 8),
+                    "rules[1].exec starts with an empty program name"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_column_counts_characters() {
+        let content = "a = \"ä\"\nb = 1";
+        assert_eq!(line_column(content, 0), (1, 1));
+        assert_eq!(line_column(content, content.find('b').unwrap()), (2, 1));
+        assert_eq!(line_column(content, content.len() + 10), (2, 6));
+        assert_eq!(line_column(content, "a = \"ä".len()), (1, 7));
+    }
+}
@@ -0,0 +1,151 @@
+// Limits on what the untrusted header may ask for, real icons stay far below
+const MAX_SIZE: usize = 1024;
+const MAX_COLORS: usize = 1024;
+const MAX_CHARS_PER_PIXEL: usize = 4;
+
+/// Minimal XPM (version 3) decoder, enough for the legacy icons some apps and
+/// `/usr/share/pixmaps` still ship. Returns `(width, height, rgba)`.
+pub fn decode(content: &str) -> Option<(u32, u32, Vec<u8>)> {
+    // everything meaningful in an XPM file lives inside double quoted C strings
+    let mut strings = content
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .filter(|line| !line.is_empty());
+
+    let mut header = strings.next()?.split_whitespace();
+    let width: usize = header.next()?.parse().ok()?;
+    let height: usize = header.next()?.parse().ok()?;
+    let colors: usize = header.next()?.parse().ok()?;
+    let chars_per_pixel: usize = header.next()?.parse().ok()?;
+    // the header is untrusted, anything this large is no icon
+    if !(1..=MAX_SIZE).contains(&width)
+        || !(1..=MAX_SIZE).contains(&height)
+        || colors > MAX_COLORS
+        || !(1..=MAX_CHARS_PER_PIXEL).contains(&chars_per_pixel)
+    {
+        return None;
+    }
+    let size = width.checked_mul(height)?.checked_mul(4)?;
+    // the pixel rows alone take this much of the file, so a truncated one fails before
+    // anything is allocated for it
+    if content.len() < width.checked_mul(height)?.checked_mul(chars_per_pixel)? {
+        return None;
+    }
+
+    let mut palette = std::collections::HashMap::new();
+    for _ in 0..colors {
+        let line = strings.next()?;
+        let key = line.get(..chars_per_pixel)?;
+        let mut parts = line.get(chars_per_pixel..)?.split_whitespace();
+        let mut color = None;
+        // "<chars> c #rrggbb m white ...", prefer the color visual
+        while let Some(kind) = parts.next() {
+            let value = parts.next()?;
+            if kind == "c" || color.is_none() {
+                color = Some(parse_color(value));
+            }
+        }
+        palette.insert(key, color.unwrap_or([0, 0, 0, 0]));
+    }
+
+    let mut pixels = Vec::with_capacity(size);
+    for _ in 0..height {
+        let row = strings.next()?;
+        for x in 0..width {
+            let key = row.get(x * chars_per_pixel..(x + 1) * chars_per_pixel)?;
+            pixels.extend_from_slice(palette.get(key).unwrap_or(&[0, 0, 0, 0]));
+        }
+    }
+
+    Some((width as u32, height as u32, pixels))
+}
+
+fn parse_color(value: &str) -> [u8; 4] {
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize, len: usize| {
+            u16::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16)
+                .ok()
+                .map(|v| match len {
+                    1 => (v * 17) as u8,
+                    2 => v as u8,
+                    // 12 and 16 bit per channel colors, keep the most significant byte
+                    _ => (v >> ((len - 2) * 4)) as u8,
+                })
+        };
+        let len = hex.len() / 3;
+        if hex.len() % 3 == 0 && (1..=4).contains(&len) {
+            if let (Some(r), Some(g), Some(b)) = (channel(0, len), channel(1, len), channel(2, len))
+            {
+                return [r, g, b, u8::MAX];
+            }
+        }
+        return [0, 0, 0, u8::MAX];
+    }
+
+    match value.to_lowercase().as_str() {
+        "none" => [0, 0, 0, 0],
+        "white" => [255, 255, 255, 255],
+        "red" => [255, 0, 0, 255],
+        "green" => [0, 255, 0, 255],
+        "blue" => [0, 0, 255, 255],
+        "yellow" => [255, 255, 0, 255],
+        "gray" | "grey" => [190, 190, 190, 255],
+        _ => [0, 0, 0, 255],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICON: &str = r#"/* XPM */
+static char * icon[] = {
+"2 2 3 1",
+"  c None",
+". c #ff0000",
+"x c #00F m white",
+" .",
+"x."};"#;
+
+    #[test]
+    fn decodes_pixels() {
+        let (width, height, pixels) = decode(ICON).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(
+            pixels,
+            [0, 0, 0, 0, 255, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_headers() {
+        assert!(decode("\"100000 100000 1 1\"").is_none());
+        assert!(decode("\"1025 1 1 1\"").is_none());
+        assert!(decode("\"1 1 100000 1\"").is_none());
+        assert!(decode("\"1 1 1 100\"").is_none());
+        let overflow = format!("\"{0} {0} 1 1\"", usize::MAX);
+        assert!(decode(&overflow).is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        assert!(decode("\"2 2 1\"").is_none());
+        assert!(decode("\"2 2 1 1\", \"  c None\", \"  \"").is_none());
+        assert!(decode("\"1024 1024 1 1\", \"  c None\"").is_none());
+        assert!(decode("\"2 2 2 1\", \"  c None\"").is_none());
+    }
+
+    #[test]
+    fn rejects_short_rows() {
+        assert!(decode("\"2 1 1 1\", \". c red\", \".\"").is_none());
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#fff"), [255, 255, 255, 255]);
+        assert_eq!(parse_color("#ffff00000000"), [255, 0, 0, 255]);
+        assert_eq!(parse_color("None"), [0, 0, 0, 0]);
+        assert_eq!(parse_color("#12"), [0, 0, 0, 255]);
+    }
+}
@@ -1,3 +1,4 @@
+use futures::SinkExt;
 use iced::{Color, Element, Task};
 use iced_layershell::build_pattern::daemon;
 use iced_layershell::reexport::{Anchor, KeyboardInteractivity, Layer};
@@ -6,8 +7,10 @@ use iced_layershell::to_layer_message;
 
 use crate::handler::notification::NotificationHandler;
 
-pub fn start() -> Result<(), iced_layershell::Error> {
-    let config = crate::data::config::Config::default(); //TODO: change to read from file
+pub fn start(
+    config: crate::data::config::Config,
+    config_path: std::path::PathBuf,
+) -> Result<(), iced_layershell::Error> {
     let settings = Settings {
         layer_settings: LayerShellSettings {
             anchor: Anchor::Top | Anchor::Right,
@@ -23,7 +26,7 @@ pub fn start() -> Result<(), iced_layershell::Error> {
         ..Default::default()
     };
     daemon(
-        move || IcedWaylandWidgetCenter::new(config.clone()),
+        move || IcedWaylandWidgetCenter::new(config.clone(), config_path.clone()),
         "IcedWaylandWidgetCenter",
         IcedWaylandWidgetCenter::update,
         IcedWaylandWidgetCenter::view,
@@ -39,30 +42,111 @@ pub fn start() -> Result<(), iced_layershell::Error> {
 
 use indexmap::IndexMap;
 
+/// Quiet time after a wheel event before scrolling triggers a binding again
+const SCROLL_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
 pub struct IcedWaylandWidgetCenter {
     pub config: crate::data::config::Config,
+    pub config_path: std::path::PathBuf,
     pub notification_ids:
         IndexMap<iced::window::Id, crate::gui::elements::notification::NotificationWindowInfo>,
     pub precalc: crate::data::notification::PreCalc,
+    pub dbus_sender:
+        Option<tokio::sync::mpsc::UnboundedSender<crate::data::notification::NotificationAction>>,
+    pub timers: crate::handler::timers::ExpiryTimers,
+    /// notifications still resolving their icon, by id with the serial of the newest `Notify`
+    pub pending_notifications: std::collections::HashMap<u32, u64>,
+    pub notify_serial: u64,
+    /// notification window under the pointer
+    pub hovered: Option<iced::window::Id>,
+    /// notification window showing its context menu instead of the content
+    pub context_menu: Option<iced::window::Id>,
+    pub history: crate::data::history::History,
+    pub center: Option<crate::gui::notification::NotificationCenter>,
+    pub dnd: crate::handler::dnd::DoNotDisturb,
+    /// whether do not disturb was active when last checked, to notice it ending
+    pub dnd_active: bool,
+    /// notifications held back by do not disturb, by id
+    pub held_notifications:
+        IndexMap<u32, crate::gui::elements::notification::NotificationWindowInfo>,
+    /// a `SaveHistory` is on its way, changes until then are written with it
+    pub history_save_scheduled: bool,
+    /// last wheel event over a notification, a single flick sends a burst of them
+    pub last_scroll: Option<std::time::Instant>,
 }
 
 #[to_layer_message(multi)]
 #[derive(Debug, Clone)]
 pub enum Message {
-    Close(iced::window::Id),
-    CloseByContentId(u32),
+    Close(iced::window::Id, crate::data::notification::CloseReason),
+    CloseByContentId(u32, crate::data::notification::CloseReason),
+    /// expiry timer of a notification id fired, with the generation it was started with
+    Expire(u32, u64),
     TestMessage,
     MoveNotifications,
     Notify(crate::data::notification::Notification),
+    ShowNotification(
+        u64,
+        Box<crate::gui::elements::notification::NotificationWindowInfo>,
+    ),
+    InvokeAction(iced::window::Id, String),
+    Mouse(iced::window::Id, crate::data::config::MouseTrigger),
+    CopyBody(iced::window::Id),
+    OpenLink(String),
+    CursorEntered(iced::window::Id),
+    CursorLeft(iced::window::Id),
+    ConfigReload(Box<crate::data::config::Config>),
+    HistoryQuery(
+        crate::data::history::HistoryQuery,
+        crate::handler::ipc::Reply,
+    ),
+    ToggleCenter,
+    /// redraws the center so the ages of its entries stay current
+    CenterTick,
+    Key(
+        iced::window::Id,
+        iced::keyboard::Key,
+        iced::keyboard::Modifiers,
+    ),
+    SelectHistory(u64),
+    DismissHistory(u64),
+    ClearHistory,
+    InvokeHistoryAction(u64, String),
+    Dnd(crate::handler::dnd::DndCommand, crate::handler::ipc::Reply),
+    /// checks whether a schedule or snooze ended do not disturb
+    DndTick,
+    SaveHistory,
+    NotificationServerReady(
+        tokio::sync::mpsc::UnboundedSender<crate::data::notification::NotificationAction>,
+    ),
 }
 
 impl IcedWaylandWidgetCenter {
-    fn new(cfg: crate::data::config::Config) -> (Self, Task<Message>) {
+    fn new(
+        cfg: crate::data::config::Config,
+        config_path: std::path::PathBuf,
+    ) -> (Self, Task<Message>) {
+        crate::data::shared::set_capabilities(&cfg);
+        let dnd = crate::handler::dnd::DoNotDisturb::new(&cfg.dnd);
         (
             Self {
                 precalc: crate::data::notification::PreCalc::generate(&cfg),
+                history: crate::data::history::History::load(cfg.history.clone()),
                 config: cfg,
+                config_path,
                 notification_ids: IndexMap::new(),
+                dbus_sender: None,
+                timers: crate::handler::timers::ExpiryTimers::default(),
+                pending_notifications: std::collections::HashMap::new(),
+                notify_serial: 0,
+                hovered: None,
+                context_menu: None,
+                center: None,
+                dnd_active: dnd.active(&cfg.dnd),
+                dnd,
+                held_notifications: IndexMap::new(),
+                history_save_scheduled: false,
+                last_scroll: None,
             },
             Task::none(),
         )
@@ -71,26 +155,48 @@ impl IcedWaylandWidgetCenter {
     fn subscription(&self) -> iced::Subscription<Message> {
         let notification_subscription = if self.config.notifications.enable {
             iced::Subscription::run(|| {
-                iced::stream::channel(100, |sender| async move {
-                    let builder = zbus::connection::Builder::session()
-                        .unwrap()
-                        .name("org.freedesktop.Notifications")
-                        .unwrap()
-                        .serve_at(
-                            "/org/freedesktop/Notifications",
-                            NotificationHandler::new(sender),
-                        )
-                        .unwrap();
-                    let _connection = match builder.build().await {
-                        Ok(connection) => connection,
-                        Err(e) => {
-                            log::error!("Failed to build the connection: {e}");
-                            std::process::exit(1);
+                iced::stream::channel(
+                    100,
+                    |mut sender: futures::channel::mpsc::Sender<_>| async move {
+                        let builder = zbus::connection::Builder::session()
+                            .unwrap()
+                            .name("org.freedesktop.Notifications")
+                            .unwrap()
+                            .serve_at(
+                                "/org/freedesktop/Notifications",
+                                NotificationHandler::new(sender.clone()),
+                            )
+                            .unwrap();
+                        let connection = match builder.build().await {
+                            Ok(connection) => connection,
+                            Err(e) => {
+                                log::error!("Failed to build the connection: {e}");
+                                std::process::exit(1);
+                            }
+                        };
+
+                        let (action_sender, mut action_receiver) =
+                            tokio::sync::mpsc::unbounded_channel();
+                        if let Err(e) = sender
+                            .send(Message::NotificationServerReady(action_sender))
+                            .await
+                        {
+                            log::error!("Failed to send message: {e}");
                         }
-                    };
-                    futures::future::pending::<()>().await;
-                    unreachable!()
-                })
+                        while let Some(action) = action_receiver.recv().await {
+                            if let Err(e) =
+                                crate::handler::notification::emit_action(&connection, action).await
+                            {
+                                log::error!(
+                                    "Failed to emit a D-Bus signal: {}",
+                                    zbus::fdo::Error::from(e)
+                                );
+                            }
+                        }
+                        futures::future::pending::<()>().await;
+                        unreachable!()
+                    },
+                )
             })
         } else {
             iced::Subscription::none()
@@ -133,13 +239,72 @@ impl IcedWaylandWidgetCenter {
             )
         });
 
+        let config_subscription = iced::Subscription::run_with(self.config_path.clone(), |path| {
+            let path = path.clone();
+            iced::stream::channel(10, |sender| async move {
+                if let Err(e) = crate::handler::config::watch(path, sender).await {
+                    log::error!("Failed to watch the config file: {e}");
+                }
+            })
+        });
+
+        let center_subscription = if self.center.is_some() {
+            iced::time::every(std::time::Duration::from_secs(30)).map(|_| Message::CenterTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let dnd_subscription = if self.dnd.timed(&self.config.dnd) {
+            iced::time::every(std::time::Duration::from_secs(30)).map(|_| Message::DndTick)
+        } else {
+            iced::Subscription::none()
+        };
+
         iced::Subscription::batch([
             notification_subscription,
             ipc_subscription,
-            iced::event::listen_with(|event, _status, id| match event {
-                iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
-                    iced::mouse::Button::Right,
-                )) => Some(Message::Close(id)),
+            config_subscription,
+            center_subscription,
+            dnd_subscription,
+            iced::event::listen_with(|event, status, id| match event {
+                // presses on buttons and links are captured, those clicks are theirs
+                iced::Event::Mouse(iced::mouse::Event::ButtonPressed(button))
+                    if status == iced::event::Status::Ignored =>
+                {
+                    let trigger = match button {
+                        iced::mouse::Button::Left => crate::data::config::MouseTrigger::Left,
+                        iced::mouse::Button::Middle => crate::data::config::MouseTrigger::Middle,
+                        iced::mouse::Button::Right => crate::data::config::MouseTrigger::Right,
+                        _ => return None,
+                    };
+                    Some(Message::Mouse(id, trigger))
+                }
+                iced::Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) => {
+                    let (iced::mouse::ScrollDelta::Lines { y, .. }
+                    | iced::mouse::ScrollDelta::Pixels { y, .. }) = delta;
+                    if y > 0.0 {
+                        Some(Message::Mouse(
+                            id,
+                            crate::data::config::MouseTrigger::ScrollUp,
+                        ))
+                    } else if y < 0.0 {
+                        Some(Message::Mouse(
+                            id,
+                            crate::data::config::MouseTrigger::ScrollDown,
+                        ))
+                    } else {
+                        None
+                    }
+                }
+                iced::Event::Mouse(iced::mouse::Event::CursorEntered) => {
+                    Some(Message::CursorEntered(id))
+                }
+                iced::Event::Mouse(iced::mouse::Event::CursorLeft) => Some(Message::CursorLeft(id)),
+                iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                    key, modifiers, ..
+                }) if status == iced::event::Status::Ignored => {
+                    Some(Message::Key(id, key, modifiers))
+                }
                 _ => None,
             }),
         ])
@@ -147,43 +312,209 @@ impl IcedWaylandWidgetCenter {
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Close(id) => {
-                self.notification_ids.shift_remove(&id);
-
-                Task::batch([
-                    Task::done(Message::RemoveWindow(id)),
-                    Task::done(Message::MoveNotifications),
-                ])
-            }
-            Message::CloseByContentId(notification_id) => {
+            Message::Close(id, reason) => self.close_notification(id, reason),
+            Message::CloseByContentId(notification_id, reason) => {
+                // an update still resolving its icon is dropped once it arrives
+                let pending = self
+                    .pending_notifications
+                    .remove(&notification_id)
+                    .is_some();
                 if let Some((window_id, _)) = self
                     .notification_ids
                     .iter()
                     .find(|(_, info)| info.notification.notification_id == notification_id)
                     .map(|(k, v)| (*k, v))
                 {
-                    return Task::done(Message::Close(window_id));
+                    return Task::done(Message::Close(window_id, reason));
+                }
+                if let Some(info) = self.held_notifications.shift_remove(&notification_id) {
+                    self.history
+                        .close(info.notification.notification_id, reason);
+                    self.send_action(crate::data::notification::NotificationAction::ActionClose {
+                        notification_id,
+                        reason,
+                    });
+                    return Task::batch([
+                        self.save_history(),
+                        crate::handler::hooks::run(
+                            &self.config.hooks,
+                            crate::handler::hooks::HookEvent::Close(reason),
+                            &info.notification,
+                        ),
+                    ]);
                 }
+                // closed before it was ever shown
+                if pending {
+                    self.send_action(crate::data::notification::NotificationAction::ActionClose {
+                        notification_id,
+                        reason,
+                    });
+                }
+                Task::none()
+            }
+            Message::Expire(notification_id, generation) => {
+                if !self.timers.expire(notification_id, generation) {
+                    return Task::none();
+                }
+                let window_id = self
+                    .notification_ids
+                    .iter()
+                    .find(|(_, info)| info.notification.notification_id == notification_id)
+                    .map(|(window_id, _)| *window_id);
+                match window_id {
+                    Some(window_id) => self.close_notification(
+                        window_id,
+                        crate::data::notification::CloseReason::Expired,
+                    ),
+                    None => Task::none(),
+                }
+            }
+            Message::CursorEntered(id) => {
+                self.hovered = Some(id);
+                if self.config.notifications.pause_on_hover {
+                    if let Some(info) = self.notification_ids.get(&id) {
+                        self.timers.pause(info.notification.notification_id);
+                    }
+                }
+                Task::none()
+            }
+            Message::CursorLeft(id) => {
+                if self.hovered == Some(id) {
+                    self.hovered = None;
+                }
+                match self.notification_ids.get(&id) {
+                    Some(info) => self.timers.resume(
+                        info.notification.notification_id,
+                        std::time::Duration::from_secs_f64(
+                            self.config.notifications.hover_resume_timeout,
+                        ),
+                    ),
+                    None => Task::none(),
+                }
+            }
+            Message::Mouse(id, trigger) => {
+                // widget windows have no bindings
+                if !self.notification_ids.contains_key(&id) {
+                    return Task::none();
+                }
+                // a click next to the menu entries only closes the menu
+                if self.context_menu == Some(id) {
+                    self.context_menu = None;
+                    return Task::none();
+                }
+                // the wheel events of one flick trigger the binding once, a dismiss would
+                // otherwise also close the notifications moving under the pointer
+                if matches!(
+                    trigger,
+                    crate::data::config::MouseTrigger::ScrollUp
+                        | crate::data::config::MouseTrigger::ScrollDown
+                ) {
+                    let now = std::time::Instant::now();
+                    let last_scroll = self.last_scroll.replace(now);
+                    if last_scroll.is_some_and(|last| now - last < SCROLL_DEBOUNCE) {
+                        return Task::none();
+                    }
+                }
+                self.mouse_action(id, self.config.notifications.mouse.action(trigger))
+            }
+            Message::CopyBody(id) => {
+                if self.context_menu == Some(id) {
+                    self.context_menu = None;
+                }
+                match self.notification_ids.get(&id) {
+                    Some(info) => iced::clipboard::write(crate::data::markup::plain_text(
+                        &info.notification.body,
+                    )),
+                    None => Task::none(),
+                }
+            }
+            Message::OpenLink(url) => {
+                // bodies come from any client, only hand plain web and mail links to xdg-open
+                if !crate::data::markup::is_allowed_link(&url) {
+                    log::warn!("Refusing to open link {url:?}");
+                    return Task::none();
+                }
+                Task::future(async move {
+                    match tokio::process::Command::new("xdg-open")
+                        .arg(&url)
+                        .status()
+                        .await
+                    {
+                        Ok(status) if !status.success() => {
+                            log::error!("xdg-open failed to open {url}: {status}")
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::error!("Failed to run xdg-open for {url}: {e}"),
+                    }
+                })
+                .discard()
+            }
+            Message::InvokeAction(id, action_key) => {
+                if self.context_menu == Some(id) {
+                    self.context_menu = None;
+                }
+                let Some(info) = self.notification_ids.get(&id) else {
+                    return Task::none();
+                };
+                let resident = info.notification.hints.resident;
+                let hook = crate::handler::hooks::run(
+                    &self.config.hooks,
+                    crate::handler::hooks::HookEvent::Action(action_key.clone()),
+                    &info.notification,
+                );
+                self.send_action(
+                    crate::data::notification::NotificationAction::ActionInvoked {
+                        notification_id: info.notification.notification_id,
+                        action_key,
+                    },
+                );
+                // resident notifications stay on screen until explicitly closed
+                if resident {
+                    return hook;
+                }
+                Task::batch([
+                    hook,
+                    self.close_notification(id, crate::data::notification::CloseReason::Dismissed),
+                ])
+            }
+            Message::NotificationServerReady(sender) => {
+                self.dbus_sender = Some(sender);
                 Task::none()
             }
             Message::MoveNotifications => {
                 let mut move_notifications: Vec<Task<Message>> = Vec::new();
 
-                for (position, (window_id, _)) in self.notification_ids.iter().enumerate() {
-                    let offset: i32 = {
-                        (self.config.notifications.height as i32 * position as i32)
-                            + (self.config.notifications.vertical_margin * position as i32)
-                            + self.config.notifications.vertical_margin
+                // rules may put notifications on other edges, each location is its own stack
+                let mut offsets: Vec<(iced_layershell::reexport::Anchor, i32)> = Vec::new();
+                for (window_id, info) in self.notification_ids.iter() {
+                    let location = info.location(&self.config);
+                    let index = match offsets.iter().position(|(anchor, _)| *anchor == location) {
+                        Some(index) => index,
+                        None => {
+                            offsets.push((location, self.config.notifications.vertical_margin));
+                            offsets.len() - 1
+                        }
+                    };
+                    let offset = &mut offsets[index].1;
+                    let (top, bottom) = if location
+                        .contains(iced_layershell::reexport::Anchor::Bottom)
+                        && !location.contains(iced_layershell::reexport::Anchor::Top)
+                    {
+                        (self.config.notifications.vertical_margin, *offset)
+                    } else {
+                        (*offset, self.config.notifications.vertical_margin)
                     };
                     move_notifications.push(Task::done(Message::MarginChange {
                         id: *window_id,
                         margin: (
-                            offset,
+                            top,
                             self.config.notifications.horizontal_margin,
-                            self.config.notifications.vertical_margin,
+                            bottom,
                             self.config.notifications.horizontal_margin,
                         ),
                     }));
+                    *offset += self.notification_height(info) as i32
+                        + self.config.notifications.vertical_margin;
                 }
 
                 if !move_notifications.is_empty() {
@@ -191,25 +522,157 @@ impl IcedWaylandWidgetCenter {
                 }
                 Task::none()
             }
+            Message::HistoryQuery(query, reply) => {
+                let now = crate::data::history::now();
+                let answer: String = self
+                    .history
+                    .query(&query)
+                    .into_iter()
+                    .map(|entry| entry.to_line(now) + "\n")
+                    .collect();
+                reply.send(answer);
+                Task::none()
+            }
+            Message::ToggleCenter => crate::gui::notification::toggle(self),
+            Message::CenterTick => Task::none(),
+            Message::Key(id, key, modifiers) => {
+                // only the center takes keyboard focus
+                if self.center.as_ref().map(|center| center.window_id) != Some(id) {
+                    return Task::none();
+                }
+                crate::gui::notification::key(self, key, modifiers)
+            }
+            Message::SelectHistory(entry_id) => {
+                if let Some(center) = &mut self.center {
+                    center.selected = Some(entry_id);
+                }
+                Task::none()
+            }
+            Message::DismissHistory(entry_id) => crate::gui::notification::dismiss(self, entry_id),
+            Message::ClearHistory => {
+                if let Some(center) = &mut self.center {
+                    center.selected = None;
+                }
+                // held back notifications are only listed here, clearing them closes them
+                let held: Vec<u32> = self
+                    .history
+                    .query(&crate::data::history::HistoryQuery::default())
+                    .into_iter()
+                    .filter(|entry| entry.close_reason.is_none())
+                    .map(|entry| entry.notification_id)
+                    .filter(|id| self.held_notifications.contains_key(id))
+                    .collect();
+                let mut tasks: Vec<Task<Message>> = held
+                    .into_iter()
+                    .map(|id| {
+                        self.close_held(id, crate::data::notification::CloseReason::Dismissed)
+                    })
+                    .collect();
+                self.history.clear();
+                tasks.push(self.save_history());
+                Task::batch(tasks)
+            }
+            Message::InvokeHistoryAction(entry_id, action_key) => {
+                crate::gui::notification::invoke_action(self, entry_id, action_key)
+            }
+            Message::Dnd(command, reply) => {
+                self.dnd.apply(command, &self.config.dnd);
+                reply.send(self.dnd.status(&self.config.dnd) + "\n");
+                self.check_dnd()
+            }
+            Message::DndTick => self.check_dnd(),
+            Message::SaveHistory => {
+                self.history_save_scheduled = false;
+                match self.history.snapshot() {
+                    Some(snapshot) => {
+                        Task::future(tokio::task::spawn_blocking(move || snapshot.write()))
+                            .discard()
+                    }
+                    None => Task::none(),
+                }
+            }
             Message::TestMessage => {
                 println!("TestMessage");
                 Task::none()
             }
             Message::Notify(notification) => {
-                crate::handler::notification::handle_notification(self, notification)
+                // icons resolve concurrently, only the newest update of a notification is shown
+                self.notify_serial += 1;
+                let serial = self.notify_serial;
+                self.pending_notifications
+                    .insert(notification.notification_id, serial);
+                Task::future(crate::handler::notification::load_notification(
+                    notification,
+                    self.config.rules.clone(),
+                    self.config.notifications.respect_notification_icon,
+                    self.precalc.image_size as u32,
+                ))
+                .and_then(move |info| Task::done(Message::ShowNotification(serial, Box::new(info))))
+            }
+            Message::ShowNotification(serial, info) => {
+                let id = info.notification.notification_id;
+                if self.pending_notifications.get(&id) != Some(&serial) {
+                    return Task::none();
+                }
+                self.pending_notifications.remove(&id);
+                crate::handler::notification::handle_notification(self, *info)
+            }
+            Message::ConfigReload(config) => {
+                log::info!("Applying reloaded config");
+                self.config = *config;
+                self.precalc = crate::data::notification::PreCalc::generate(&self.config);
+                crate::data::shared::set_capabilities(&self.config);
+                self.history.set_config(self.config.history.clone());
+
+                let mut tasks: Vec<Task<Message>> = self
+                    .notification_ids
+                    .iter()
+                    .map(|(window_id, info)| {
+                        Task::done(Message::AnchorSizeChange {
+                            id: *window_id,
+                            anchor: info.location(&self.config),
+                            size: (
+                                self.config.notifications.width,
+                                self.notification_height(info),
+                            ),
+                        })
+                    })
+                    .collect();
+                if let Some(center) = &self.center {
+                    tasks.push(Task::done(Message::AnchorSizeChange {
+                        id: center.window_id,
+                        anchor: self.config.center.location,
+                        size: (self.config.center.width, self.config.center.height),
+                    }));
+                }
+                tasks.push(Task::done(Message::MoveNotifications));
+                tasks.push(self.save_history());
+                tasks.push(self.check_dnd());
+                Task::batch(tasks)
             }
             _ => unreachable!(),
         }
     }
 
     fn view(&self, id: iced::window::Id) -> Element<Message> {
+        if self.center.as_ref().map(|center| center.window_id) == Some(id) {
+            return crate::gui::notification::view(self);
+        }
         let (notification_window_info, _) = self.id_info(id);
-        let notification: iced::widget::Container<Message> =
+        let notification: Element<Message> =
             if let Some(notification_window_info) = notification_window_info {
-                crate::gui::elements::notification::body(self, notification_window_info)
+                crate::gui::elements::notification::body(self, id, notification_window_info)
             } else {
                 iced::widget::container(iced::widget::horizontal_space())
-                    .style(move |_| crate::gui::elements::style::notification_style(&self.config))
+                    .style(move |_| {
+                        crate::gui::elements::style::notification_style(
+                            &self.config,
+                            self.config
+                                .notifications
+                                .style(crate::data::notification::Urgency::Normal),
+                        )
+                    })
+                    .into()
             };
         iced::widget::stack![notification]
             //.padding(10)
@@ -220,6 +683,175 @@ impl IcedWaylandWidgetCenter {
             .into()
     }
 
+    /// Removes the notification window and lets the sender know why it was closed
+    pub fn close_notification(
+        &mut self,
+        id: iced::window::Id,
+        reason: crate::data::notification::CloseReason,
+    ) -> Task<Message> {
+        if self.hovered == Some(id) {
+            self.hovered = None;
+        }
+        if self.context_menu == Some(id) {
+            self.context_menu = None;
+        }
+        let mut hook = Task::none();
+        if let Some(info) = self.notification_ids.shift_remove(&id) {
+            self.timers.cancel(info.notification.notification_id);
+            self.history
+                .close(info.notification.notification_id, reason);
+            self.send_action(crate::data::notification::NotificationAction::ActionClose {
+                notification_id: info.notification.notification_id,
+                reason,
+            });
+            hook = crate::handler::hooks::run(
+                &self.config.hooks,
+                crate::handler::hooks::HookEvent::Close(reason),
+                &info.notification,
+            );
+        }
+
+        Task::batch([
+            Task::done(Message::RemoveWindow(id)),
+            Task::done(Message::MoveNotifications),
+            self.save_history(),
+            hook,
+        ])
+    }
+
+    /// Closes a notification held back by do not disturb, as closing its window would
+    pub fn close_held(
+        &mut self,
+        notification_id: u32,
+        reason: crate::data::notification::CloseReason,
+    ) -> Task<Message> {
+        let Some(info) = self.held_notifications.shift_remove(&notification_id) else {
+            return Task::none();
+        };
+        self.history.close(notification_id, reason);
+        self.send_action(crate::data::notification::NotificationAction::ActionClose {
+            notification_id,
+            reason,
+        });
+        Task::batch([
+            crate::handler::hooks::run(
+                &self.config.hooks,
+                crate::handler::hooks::HookEvent::Close(reason),
+                &info.notification,
+            ),
+            self.save_history(),
+        ])
+    }
+
+    /// Shows the held back notifications once do not disturb ended
+    fn check_dnd(&mut self) -> Task<Message> {
+        let active = self.dnd.active(&self.config.dnd);
+        let ended = self.dnd_active && !active;
+        self.dnd_active = active;
+        if !ended {
+            return Task::none();
+        }
+        let held: Vec<_> = self
+            .held_notifications
+            .drain(..)
+            .map(|(_, info)| info)
+            .collect();
+        Task::batch(
+            held.into_iter()
+                .map(|info| crate::handler::notification::show_notification(self, info)),
+        )
+    }
+
+    /// Writes the history to disk on the blocking pool if it changed, a moment later so a
+    /// burst of notifications is written once
+    pub fn save_history(&mut self) -> Task<Message> {
+        if self.history_save_scheduled {
+            return Task::none();
+        }
+        self.history_save_scheduled = true;
+        Task::perform(
+            tokio::time::sleep(std::time::Duration::from_secs(2)),
+            |_| Message::SaveHistory,
+        )
+    }
+
+    /// Notifications with action buttons get an extra row below the body, expanded ones
+    /// twice the room for it
+    pub fn notification_height(
+        &self,
+        info: &crate::gui::elements::notification::NotificationWindowInfo,
+    ) -> u32 {
+        let height = if info.expanded {
+            self.config.notifications.height * 2
+        } else {
+            self.config.notifications.height
+        };
+        if info.notification.action_buttons().is_empty() {
+            height
+        } else {
+            height + self.precalc.actions_height
+        }
+    }
+
+    fn mouse_action(
+        &mut self,
+        id: iced::window::Id,
+        action: crate::data::config::MouseAction,
+    ) -> Task<Message> {
+        use crate::data::config::MouseAction;
+        use crate::data::notification::CloseReason;
+
+        match action {
+            MouseAction::None => Task::none(),
+            MouseAction::Dismiss => self.close_notification(id, CloseReason::Dismissed),
+            MouseAction::DismissAll => {
+                let ids: Vec<iced::window::Id> = self.notification_ids.keys().copied().collect();
+                Task::batch(
+                    ids.into_iter()
+                        .map(|id| self.close_notification(id, CloseReason::Dismissed)),
+                )
+            }
+            MouseAction::InvokeDefault => match self.notification_ids.get(&id) {
+                Some(info) if info.notification.has_default_action() => {
+                    Task::done(Message::InvokeAction(id, "default".to_string()))
+                }
+                _ => Task::none(),
+            },
+            MouseAction::OpenContextMenu => {
+                self.context_menu = Some(id);
+                Task::none()
+            }
+            MouseAction::CopyBody => Task::done(Message::CopyBody(id)),
+            MouseAction::ToggleExpand => {
+                let Some(info) = self.notification_ids.get_mut(&id) else {
+                    return Task::none();
+                };
+                info.expanded = !info.expanded;
+                let info = &self.notification_ids[&id];
+                let height = self.notification_height(info);
+                Task::batch([
+                    Task::done(Message::AnchorSizeChange {
+                        id,
+                        anchor: info.location(&self.config),
+                        size: (self.config.notifications.width, height),
+                    }),
+                    Task::done(Message::MoveNotifications),
+                ])
+            }
+        }
+    }
+
+    pub fn send_action(&self, action: crate::data::notification::NotificationAction) {
+        match &self.dbus_sender {
+            Some(sender) => {
+                if let Err(e) = sender.send(action) {
+                    log::error!("Failed to send notification action: {e}");
+                }
+            }
+            None => log::warn!("Notification server is not ready, dropping {action:?}"),
+        }
+    }
+
     fn id_info(
         &self,
         id: iced::window::Id,
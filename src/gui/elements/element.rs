@@ -1 +1,135 @@
+use crate::gui::app::Message;
 
+/// Text button in the colors of `style`, disabled without a message
+pub fn button(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    style: crate::data::config::NotificationStyle,
+    label: String,
+    width: iced::Length,
+    message: Option<Message>,
+) -> iced::Element<'_, Message> {
+    iced::widget::button(
+        iced::widget::text(label)
+            .size(iwwc.precalc.font_size_action)
+            .align_x(iced::alignment::Horizontal::Center)
+            .width(width),
+    )
+    .on_press_maybe(message)
+    .width(width)
+    .style(move |_, status| {
+        crate::gui::elements::style::action_button_style(&iwwc.config, style, status)
+    })
+    .into()
+}
+
+pub fn group_header(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    app_name: String,
+) -> iced::Element<'_, Message> {
+    let style = iwwc
+        .config
+        .notifications
+        .style(crate::data::notification::Urgency::Normal);
+    iced::widget::text(if app_name.is_empty() {
+        "Unknown".to_string()
+    } else {
+        app_name
+    })
+    .size(iwwc.precalc.font_size_body)
+    .color(style.secondary_text_color)
+    .into()
+}
+
+/// A history entry in the center: icon, summary with its age, body and the actions that can
+/// still be invoked. Clicking it selects it.
+pub fn history_entry<'a>(
+    iwwc: &'a crate::gui::app::IcedWaylandWidgetCenter,
+    entry: &crate::data::history::HistoryEntry,
+    selected: bool,
+    now: u64,
+) -> iced::Element<'a, Message> {
+    let style = iwwc.config.notifications.style(entry.urgency);
+
+    let mut text = iced::widget::column![
+        iced::widget::row![
+            iced::widget::text(entry.summary.clone())
+                .size(iwwc.precalc.font_size_body)
+                .width(iced::Length::Fill),
+            iced::widget::text(crate::data::history::format_age(entry.received, now))
+                .size(iwwc.precalc.font_size_action)
+                .color(style.secondary_text_color),
+        ]
+        .spacing(iwwc.precalc.general_padding),
+        iced::widget::text(crate::data::markup::plain_text(&entry.body))
+            .size(iwwc.precalc.font_size_action)
+            .color(style.secondary_text_color),
+    ]
+    .spacing(iwwc.precalc.general_padding / 2.0)
+    .width(iced::Length::Fill);
+    // actions stop being valid once the notification is closed
+    if entry.close_reason.is_none() && !entry.actions.is_empty() {
+        text = text.push(
+            iced::widget::row(entry.actions.chunks_exact(2).map(|pair| {
+                button(
+                    iwwc,
+                    style,
+                    pair[1].clone(),
+                    iced::Length::Fill,
+                    Some(Message::InvokeHistoryAction(entry.id, pair[0].clone())),
+                )
+            }))
+            .spacing(iwwc.precalc.general_padding),
+        );
+    }
+
+    let content = iced::widget::row![
+        icon(iwwc, entry.icon.as_deref()),
+        text,
+        button(
+            iwwc,
+            style,
+            "×".to_string(),
+            iced::Length::Shrink,
+            Some(Message::DismissHistory(entry.id)),
+        ),
+    ]
+    .spacing(iwwc.precalc.general_padding)
+    .align_y(iced::alignment::Vertical::Top);
+
+    iced::widget::mouse_area(
+        iced::widget::container(content)
+            .padding(iwwc.precalc.general_padding)
+            .width(iced::Length::Fill)
+            .style(move |_| {
+                crate::gui::elements::style::history_entry_style(&iwwc.config, style, selected)
+            }),
+    )
+    .on_press(Message::SelectHistory(entry.id))
+    .into()
+}
+
+/// Icons are kept as the file they were resolved to, pixel data leaves an empty spot
+fn icon<'a>(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    path: Option<&std::path::Path>,
+) -> iced::Element<'a, Message> {
+    let size = iced::Length::Fixed(iwwc.precalc.image_size / 2.0);
+    let Some(path) = path else {
+        return iced::widget::Space::new(size, size).into();
+    };
+    let svg = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"));
+    if svg {
+        iced::widget::svg(path.to_path_buf())
+            .width(size)
+            .height(size)
+            .into()
+    } else {
+        iced::widget::image(path.to_path_buf())
+            .width(size)
+            .height(size)
+            .into()
+    }
+}
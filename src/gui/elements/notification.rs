@@ -1,39 +1,263 @@
 #[derive(Debug, Clone, PartialEq)]
 pub struct NotificationWindowInfo {
     pub notification: crate::data::notification::Notification,
-    pub icon: std::path::PathBuf,
+    pub icon: NotificationIcon,
+    /// `notification.body` with its markup parsed
+    pub body: Vec<crate::data::markup::Span>,
+    /// toggled by the `toggle-expand` mouse binding
+    pub expanded: bool,
+    /// what the matching `[[rules]]` changed about it
+    pub rules: crate::data::rules::RuleOutcome,
+}
+
+impl NotificationWindowInfo {
+    pub fn style(
+        &self,
+        config: &crate::data::config::Config,
+    ) -> crate::data::config::NotificationStyle {
+        self.rules
+            .restyle(config.notifications.style(self.notification.hints.urgency))
+    }
+
+    /// The edge it stacks on, a rule may put it somewhere else than the others
+    pub fn location(
+        &self,
+        config: &crate::data::config::Config,
+    ) -> iced_layershell::reexport::Anchor {
+        self.rules.location.unwrap_or(config.notifications.location)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationIcon {
+    Svg(std::path::PathBuf),
+    Raster(iced::widget::image::Handle),
+}
+
+impl NotificationIcon {
+    /// The file the icon was read from, `None` for pixel data
+    pub fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            Self::Svg(path) | Self::Raster(iced::widget::image::Handle::Path(_, path)) => {
+                Some(path)
+            }
+            Self::Raster(_) => None,
+        }
+    }
+
+    /// Chooses the widget by extension, sniffing the content for files without a known one
+    /// (e.g. temporary files apps write avatars to)
+    pub fn from_path(path: std::path::PathBuf) -> Option<Self> {
+        enum Kind {
+            Svg,
+            Raster,
+            Xpm,
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        let kind = match extension.as_deref() {
+            Some("svg" | "svgz") => Kind::Svg,
+            Some("png" | "jpg" | "jpeg") => Kind::Raster,
+            Some("xpm") => Kind::Xpm,
+            _ => {
+                use std::io::Read;
+
+                let mut magic = [0u8; 64];
+                let read = std::fs::File::open(&path)
+                    .and_then(|mut file| file.read(&mut magic))
+                    .ok()?;
+                let magic = &magic[..read];
+                if magic.starts_with(b"\x89PNG") || magic.starts_with(&[0xFF, 0xD8, 0xFF]) {
+                    Kind::Raster
+                } else if magic.starts_with(b"/* XPM */") {
+                    Kind::Xpm
+                } else if magic.starts_with(b"<svg")
+                    || (magic.starts_with(b"<?xml")
+                        && String::from_utf8_lossy(magic).contains("<svg"))
+                {
+                    Kind::Svg
+                } else {
+                    return None;
+                }
+            }
+        };
+
+        match kind {
+            Kind::Svg => Some(Self::Svg(path)),
+            Kind::Raster => Some(Self::Raster(iced::widget::image::Handle::from_path(path))),
+            Kind::Xpm => {
+                let content = std::fs::read_to_string(&path).ok()?;
+                let (width, height, pixels) = crate::data::xpm::decode(&content)?;
+                Some(Self::Raster(iced::widget::image::Handle::from_rgba(
+                    width, height, pixels,
+                )))
+            }
+        }
+    }
 }
 
 pub fn body(
     iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    id: iced::window::Id,
     window_info: NotificationWindowInfo,
-) -> iced::widget::Container<'_, crate::gui::app::Message> {
-    iced::widget::container(
-        iced::widget::row![
-            iced::widget::svg(window_info.icon.clone())
-                .width(iced::Length::Fixed(iwwc.precalc.image_size))
-                .height(iced::Length::Fixed(iwwc.precalc.image_size)),
+) -> iced::Element<'_, crate::gui::app::Message> {
+    let content = iced::widget::row![
+        icon(iwwc, &window_info.icon),
+        iced::widget::column![
             iced::widget::column![
-                iced::widget::column![
-                    iced::widget::text(window_info.notification.summary.clone())
-                        .size(iwwc.precalc.font_size_summary)
-                        .align_x(iced::alignment::Horizontal::Left),
-                ]
-                .padding(iwwc.precalc.text_summary_paddings),
-                iced::widget::column![
-                    iced::widget::text(window_info.notification.body.clone())
-                        .size(iwwc.precalc.font_size_body),
-                ]
-                .padding(iwwc.precalc.text_body_paddings),
+                iced::widget::text(window_info.notification.summary.clone())
+                    .size(iwwc.precalc.font_size_summary)
+                    .align_x(iced::alignment::Horizontal::Left),
             ]
-            .padding(iwwc.precalc.text_paddings_block)
+            .padding(iwwc.precalc.text_summary_paddings),
+            iced::widget::column![body_text(iwwc, &window_info)]
+                .padding(iwwc.precalc.text_body_paddings),
         ]
-        .align_y(iced::alignment::Vertical::Center)
-        .width(iced::Length::Fill)
-        .height(iced::Length::Fill),
-    )
-    .padding(iwwc.precalc.general_padding)
+        .padding(iwwc.precalc.text_paddings_block)
+    ]
+    .align_y(iced::alignment::Vertical::Center)
     .width(iced::Length::Fill)
-    .height(iced::Length::Fill)
-    .style(move |_| crate::gui::elements::style::notification_style(&iwwc.config))
+    .height(iced::Length::Fill);
+
+    let style = window_info.style(&iwwc.config);
+    let action_buttons = window_info.notification.action_buttons();
+    let content: iced::Element<'_, crate::gui::app::Message> = if iwwc.context_menu == Some(id) {
+        context_menu(iwwc, id, style, &window_info.notification).into()
+    } else if action_buttons.is_empty() {
+        content.into()
+    } else {
+        iced::widget::column![content, actions(iwwc, id, style, action_buttons)].into()
+    };
+
+    let notification = iced::widget::container(content)
+        .padding(iwwc.precalc.general_padding)
+        .width(iced::Length::Fill)
+        .height(iced::Length::Fill)
+        .style(move |_| crate::gui::elements::style::notification_style(&iwwc.config, style));
+
+    // clicks are handled by the mouse bindings, this only shows that the click does something
+    if window_info.notification.has_default_action()
+        && iwwc.config.notifications.mouse.left == crate::data::config::MouseAction::InvokeDefault
+    {
+        iced::widget::mouse_area(notification)
+            .interaction(iced::mouse::Interaction::Pointer)
+            .into()
+    } else {
+        notification.into()
+    }
+}
+
+fn body_text<'a>(
+    iwwc: &'a crate::gui::app::IcedWaylandWidgetCenter,
+    window_info: &NotificationWindowInfo,
+) -> iced::widget::text::Rich<'a, String, crate::gui::app::Message> {
+    let style = window_info.style(&iwwc.config);
+    let spans: Vec<iced::widget::text::Span<'a, String>> = window_info
+        .body
+        .iter()
+        .map(|span| {
+            let font = iced::Font {
+                weight: if span.bold {
+                    iced::font::Weight::Bold
+                } else {
+                    iced::font::Weight::Normal
+                },
+                style: if span.italic {
+                    iced::font::Style::Italic
+                } else {
+                    iced::font::Style::Normal
+                },
+                ..iced::Font::DEFAULT
+            };
+            iced::widget::span(span.text.clone())
+                .font(font)
+                .underline(span.underline || span.link.is_some())
+                .color_maybe(span.link.as_ref().map(|_| style.border_color))
+                .link_maybe(span.link.clone())
+        })
+        .collect();
+    iced::widget::rich_text(spans)
+        .size(iwwc.precalc.font_size_body)
+        .color(style.secondary_text_color)
+        .on_link_click(crate::gui::app::Message::OpenLink)
+}
+
+fn icon<'a>(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    icon: &NotificationIcon,
+) -> iced::Element<'a, crate::gui::app::Message> {
+    let size = iced::Length::Fixed(iwwc.precalc.image_size);
+    match icon {
+        NotificationIcon::Svg(path) => iced::widget::svg(path.clone())
+            .width(size)
+            .height(size)
+            .into(),
+        NotificationIcon::Raster(handle) => iced::widget::image(handle.clone())
+            .width(size)
+            .height(size)
+            .into(),
+    }
+}
+
+fn actions(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    id: iced::window::Id,
+    style: crate::data::config::NotificationStyle,
+    action_buttons: Vec<(String, String)>,
+) -> iced::widget::Row<'_, crate::gui::app::Message> {
+    iced::widget::row(action_buttons.into_iter().map(|(key, label)| {
+        menu_button(
+            iwwc,
+            style,
+            label,
+            crate::gui::app::Message::InvokeAction(id, key),
+        )
+    }))
+    .spacing(iwwc.precalc.general_padding)
+    .height(iced::Length::Fixed(iwwc.precalc.actions_height as f32))
+    .align_y(iced::alignment::Vertical::Center)
+}
+
+/// Replaces the content with every action, including the default one, plus copy and dismiss
+fn context_menu<'a>(
+    iwwc: &'a crate::gui::app::IcedWaylandWidgetCenter,
+    id: iced::window::Id,
+    style: crate::data::config::NotificationStyle,
+    notification: &crate::data::notification::Notification,
+) -> iced::widget::Row<'a, crate::gui::app::Message> {
+    let entries = notification
+        .actions
+        .chunks_exact(2)
+        .map(|pair| {
+            (
+                pair[1].clone(),
+                crate::gui::app::Message::InvokeAction(id, pair[0].clone()),
+            )
+        })
+        .chain([
+            ("Copy".to_string(), crate::gui::app::Message::CopyBody(id)),
+            (
+                "Dismiss".to_string(),
+                crate::gui::app::Message::Close(
+                    id,
+                    crate::data::notification::CloseReason::Dismissed,
+                ),
+            ),
+        ]);
+    iced::widget::row(entries.map(|(label, message)| menu_button(iwwc, style, label, message)))
+        .spacing(iwwc.precalc.general_padding)
+        .height(iced::Length::Fill)
+        .align_y(iced::alignment::Vertical::Center)
+}
+
+fn menu_button(
+    iwwc: &crate::gui::app::IcedWaylandWidgetCenter,
+    style: crate::data::config::NotificationStyle,
+    label: String,
+    message: crate::gui::app::Message,
+) -> iced::Element<'_, crate::gui::app::Message> {
+    crate::gui::elements::element::button(iwwc, style, label, iced::Length::Fill, Some(message))
 }
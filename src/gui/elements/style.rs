@@ -1,8 +1,11 @@
-pub fn notification_style(config: &crate::data::config::Config) -> iced::widget::container::Style {
+pub fn notification_style(
+    config: &crate::data::config::Config,
+    style: crate::data::config::NotificationStyle,
+) -> iced::widget::container::Style {
     iced::widget::container::Style {
-        text_color: Some(config.notifications.primary_text_color),
+        text_color: Some(style.primary_text_color),
         border: iced::Border {
-            color: config.notifications.border_color,
+            color: style.border_color,
             width: config.notifications.border_width,
             radius: config.notifications.border_radius,
         },
@@ -12,9 +15,48 @@ pub fn notification_style(config: &crate::data::config::Config) -> iced::widget:
             offset: iced::Vector { x: 0.0, y: 0.0 },
             blur_radius: 0.0,
         },
-        background: Some(iced::Background::Color(
-            config.notifications.background_color,
-        )),
+        background: Some(iced::Background::Color(style.background_color)),
+        snap: false,
+    }
+}
+
+/// Entries of the notification center, the selected one is outlined in the text color
+pub fn history_entry_style(
+    config: &crate::data::config::Config,
+    style: crate::data::config::NotificationStyle,
+    selected: bool,
+) -> iced::widget::container::Style {
+    let mut container = notification_style(config, style);
+    if selected {
+        container.border.color = style.primary_text_color;
+    }
+    container
+}
+
+pub fn action_button_style(
+    config: &crate::data::config::Config,
+    style: crate::data::config::NotificationStyle,
+    status: iced::widget::button::Status,
+) -> iced::widget::button::Style {
+    let background = match status {
+        iced::widget::button::Status::Hovered | iced::widget::button::Status::Pressed => {
+            style.border_color
+        }
+        _ => style.background_color,
+    };
+    iced::widget::button::Style {
+        background: Some(iced::Background::Color(background)),
+        text_color: style.primary_text_color,
+        border: iced::Border {
+            color: style.border_color,
+            width: config.notifications.border_width / 2.0,
+            radius: config.notifications.border_radius,
+        },
+        shadow: iced::Shadow {
+            color: iced::Color::TRANSPARENT,
+            offset: iced::Vector { x: 0.0, y: 0.0 },
+            blur_radius: 0.0,
+        },
         snap: false,
     }
 }
@@ -1 +1,339 @@
+use crate::gui::app::Message;
 
+/// The notification center panel listing the history, open while it exists
+#[derive(Debug)]
+pub struct NotificationCenter {
+    pub window_id: iced::window::Id,
+    /// history entry under the keyboard selection
+    pub selected: Option<u64>,
+}
+
+/// Id of the scrollable holding the history, to keep the selection in view
+fn scroll_id() -> iced::widget::scrollable::Id {
+    iced::widget::scrollable::Id::new("notification-center")
+}
+
+pub fn toggle(iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter) -> iced::Task<Message> {
+    if iwwc.center.is_some() {
+        return close(iwwc);
+    }
+
+    let window_id = iced::window::Id::unique();
+    iwwc.center = Some(NotificationCenter {
+        window_id,
+        selected: None,
+    });
+    let center = &iwwc.config.center;
+    iced::Task::done(Message::NewLayerShell {
+        settings: iced_layershell::reexport::NewLayerShellSettings {
+            size: Some((center.width, center.height)),
+            exclusive_zone: None,
+            anchor: center.location,
+            layer: center.layer,
+            margin: Some((center.margin, center.margin, center.margin, center.margin)),
+            keyboard_interactivity: iced_layershell::reexport::KeyboardInteractivity::OnDemand,
+            output_option: iced_layershell::reexport::OutputOption::LastOutput,
+            ..Default::default()
+        },
+        id: window_id,
+    })
+}
+
+pub fn close(iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter) -> iced::Task<Message> {
+    match iwwc.center.take() {
+        Some(center) => iced::Task::done(Message::RemoveWindow(center.window_id)),
+        None => iced::Task::none(),
+    }
+}
+
+/// Entries in the order they are listed: apps by their newest notification, newest first
+/// within an app
+pub fn groups(
+    history: &crate::data::history::History,
+) -> Vec<(String, Vec<&crate::data::history::HistoryEntry>)> {
+    let mut groups: indexmap::IndexMap<String, Vec<&crate::data::history::HistoryEntry>> =
+        indexmap::IndexMap::new();
+    for entry in history.query(&crate::data::history::HistoryQuery::default()) {
+        groups
+            .entry(entry.app_name.clone())
+            .or_default()
+            .push(entry);
+    }
+    groups.into_iter().collect()
+}
+
+/// Arrow keys or j/k move the selection, Enter invokes the default action, Delete dismisses
+/// the entry, Shift+Delete clears the history and Escape closes the center
+pub fn key(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    key: iced::keyboard::Key,
+    modifiers: iced::keyboard::Modifiers,
+) -> iced::Task<Message> {
+    use iced::keyboard::Key;
+    use iced::keyboard::key::Named;
+
+    let Some(selected) = iwwc.center.as_ref().map(|center| center.selected) else {
+        return iced::Task::none();
+    };
+    let ids: Vec<u64> = groups(&iwwc.history)
+        .into_iter()
+        .flat_map(|(_, entries)| entries.into_iter().map(|entry| entry.id))
+        .collect();
+    let position = selected.and_then(|selected| ids.iter().position(|id| *id == selected));
+
+    let target = match key.as_ref() {
+        Key::Named(Named::Escape) | Key::Character("q") => return close(iwwc),
+        Key::Named(Named::Delete) if modifiers.shift() => {
+            return iced::Task::done(Message::ClearHistory);
+        }
+        Key::Named(Named::Delete | Named::Backspace) | Key::Character("d") => {
+            return match selected {
+                Some(id) => iced::Task::done(Message::DismissHistory(id)),
+                None => iced::Task::none(),
+            };
+        }
+        Key::Named(Named::Enter) | Key::Named(Named::Space) => {
+            return match selected {
+                Some(id) => {
+                    iced::Task::done(Message::InvokeHistoryAction(id, "default".to_string()))
+                }
+                None => iced::Task::none(),
+            };
+        }
+        Key::Named(Named::ArrowDown) | Key::Character("j") => {
+            position.map_or(0, |position| position + 1)
+        }
+        Key::Named(Named::ArrowUp) | Key::Character("k") => {
+            position.map_or(0, |position| position.saturating_sub(1))
+        }
+        Key::Named(Named::Home) => 0,
+        Key::Named(Named::End) => ids.len().saturating_sub(1),
+        _ => return iced::Task::none(),
+    };
+    select(iwwc, &ids, target)
+}
+
+/// Highlights the entry at `index` of the listed `ids` and scrolls it into view. The offset
+/// counts the group headers as rows, rows of different heights still make it approximate.
+fn select(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    ids: &[u64],
+    index: usize,
+) -> iced::Task<Message> {
+    let group_sizes: Vec<usize> = groups(&iwwc.history)
+        .iter()
+        .map(|(_, entries)| entries.len())
+        .collect();
+    let Some(center) = &mut iwwc.center else {
+        return iced::Task::none();
+    };
+    let Some(id) = ids.get(index) else {
+        return iced::Task::none();
+    };
+    center.selected = Some(*id);
+    let (row, rows) = rendered_row(&group_sizes, index);
+    iced::widget::scrollable::snap_to(
+        scroll_id(),
+        iced::widget::scrollable::RelativeOffset {
+            x: 0.0,
+            y: row as f32 / rows.saturating_sub(1).max(1) as f32,
+        },
+    )
+}
+
+/// Row of the entry at `index` among the rendered rows, every group starts with its header,
+/// and the number of rows. The first entry of a group gives the row of the header, so
+/// scrolling to it keeps the header in view.
+fn rendered_row(group_sizes: &[usize], index: usize) -> (usize, usize) {
+    let rows = group_sizes.iter().map(|size| size + 1).sum();
+    let mut row = 0;
+    let mut index = index;
+    for size in group_sizes {
+        if index < *size {
+            return (if index == 0 { row } else { row + 1 + index }, rows);
+        }
+        index -= size;
+        row += size + 1;
+    }
+    (rows.saturating_sub(1), rows)
+}
+
+/// Forgets an entry, a selected one passes the selection on to its neighbour
+pub fn dismiss(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    entry_id: u64,
+) -> iced::Task<Message> {
+    if let Some(center) = &mut iwwc.center {
+        if center.selected == Some(entry_id) {
+            let ids: Vec<u64> = groups(&iwwc.history)
+                .into_iter()
+                .flat_map(|(_, entries)| entries.into_iter().map(|entry| entry.id))
+                .collect();
+            let position = ids.iter().position(|id| *id == entry_id);
+            center.selected = position.and_then(|position| {
+                ids.get(position + 1)
+                    .or_else(|| {
+                        position
+                            .checked_sub(1)
+                            .and_then(|previous| ids.get(previous))
+                    })
+                    .copied()
+            });
+        }
+    }
+    // a held back notification is only listed here, dismissing it closes it
+    let open = iwwc
+        .history
+        .get(entry_id)
+        .filter(|entry| entry.close_reason.is_none())
+        .map(|entry| entry.notification_id);
+    let close = match open {
+        Some(notification_id) => iwwc.close_held(
+            notification_id,
+            crate::data::notification::CloseReason::Dismissed,
+        ),
+        None => iced::Task::none(),
+    };
+    iwwc.history.remove(entry_id);
+    iced::Task::batch([close, iwwc.save_history()])
+}
+
+/// Invokes an action of a history entry. Actions are only valid while the notification is
+/// still open, a shown one goes through its window like a click on it would, a held back one
+/// is closed right here.
+pub fn invoke_action(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    entry_id: u64,
+    action_key: String,
+) -> iced::Task<Message> {
+    let Some(entry) = iwwc.history.get(entry_id) else {
+        return iced::Task::none();
+    };
+    if entry.close_reason.is_some()
+        || !entry
+            .actions
+            .chunks_exact(2)
+            .any(|pair| pair[0] == action_key)
+    {
+        return iced::Task::none();
+    }
+
+    let notification_id = entry.notification_id;
+    let window_id = iwwc
+        .notification_ids
+        .iter()
+        .find(|(_, info)| info.notification.notification_id == notification_id)
+        .map(|(window_id, _)| *window_id);
+    match window_id {
+        Some(window_id) => iced::Task::done(Message::InvokeAction(window_id, action_key)),
+        None => {
+            use crate::handler::hooks::HookEvent;
+
+            let resident = entry.resident;
+            let action_hook = match iwwc.held_notifications.get(&notification_id) {
+                Some(info) => crate::handler::hooks::run(
+                    &iwwc.config.hooks,
+                    HookEvent::Action(action_key.clone()),
+                    &info.notification,
+                ),
+                None => iced::Task::none(),
+            };
+            iwwc.send_action(
+                crate::data::notification::NotificationAction::ActionInvoked {
+                    notification_id,
+                    action_key,
+                },
+            );
+            if resident {
+                return action_hook;
+            }
+            let close = iwwc.close_held(
+                notification_id,
+                crate::data::notification::CloseReason::Dismissed,
+            );
+            iced::Task::batch([action_hook, close])
+        }
+    }
+}
+
+pub fn view(iwwc: &crate::gui::app::IcedWaylandWidgetCenter) -> iced::Element<'_, Message> {
+    let now = crate::data::history::now();
+    let selected = iwwc.center.as_ref().and_then(|center| center.selected);
+    let groups = groups(&iwwc.history);
+    let style = iwwc
+        .config
+        .notifications
+        .style(crate::data::notification::Urgency::Normal);
+
+    let header = iced::widget::row![
+        iced::widget::text("Notifications").size(iwwc.precalc.font_size_summary),
+        iced::widget::horizontal_space(),
+        crate::gui::elements::element::button(
+            iwwc,
+            style,
+            "Clear all".to_string(),
+            iced::Length::Shrink,
+            (!groups.is_empty()).then_some(Message::ClearHistory),
+        ),
+    ]
+    .align_y(iced::alignment::Vertical::Center)
+    .spacing(iwwc.precalc.general_padding);
+
+    let list: iced::Element<'_, Message> = if groups.is_empty() {
+        iced::widget::container(
+            iced::widget::text("No notifications")
+                .size(iwwc.precalc.font_size_body)
+                .color(style.secondary_text_color),
+        )
+        .center_x(iced::Length::Fill)
+        .padding(iwwc.precalc.general_padding)
+        .into()
+    } else {
+        iced::widget::scrollable(
+            iced::widget::column(groups.into_iter().map(|(app_name, entries)| {
+                iced::widget::column(
+                    std::iter::once(crate::gui::elements::element::group_header(iwwc, app_name))
+                        .chain(entries.into_iter().map(|entry| {
+                            crate::gui::elements::element::history_entry(
+                                iwwc,
+                                entry,
+                                selected == Some(entry.id),
+                                now,
+                            )
+                        })),
+                )
+                .spacing(iwwc.precalc.general_padding / 2.0)
+                .into()
+            }))
+            .spacing(iwwc.precalc.general_padding),
+        )
+        .id(scroll_id())
+        .height(iced::Length::Fill)
+        .into()
+    };
+
+    iced::widget::container(
+        iced::widget::column![header, list].spacing(iwwc.precalc.general_padding),
+    )
+    .padding(iwwc.precalc.general_padding)
+    .width(iced::Length::Fill)
+    .height(iced::Length::Fill)
+    .style(move |_| crate::gui::elements::style::notification_style(&iwwc.config, style))
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_count_group_headers() {
+        // [header, 0, 1], [header, 2], [header, 3, 4, 5]
+        let sizes = [2, 1, 3];
+        let rows: Vec<usize> = (0..6).map(|index| rendered_row(&sizes, index).0).collect();
+        assert_eq!(rows, [0, 2, 3, 5, 7, 8]);
+        assert_eq!(rendered_row(&sizes, 0).1, 9);
+        assert_eq!(rendered_row(&sizes, 6), (8, 9));
+        assert_eq!(rendered_row(&[], 0), (0, 0));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::gui::app::Message;
+
+/// Runs a command from the config, the first element is the program and the rest its
+/// arguments, no shell involved. It runs detached from the update loop, failures are logged.
+pub fn run(command: Vec<String>) -> iced::Task<Message> {
+    spawn(command, Vec::new(), None, None)
+}
+
+/// Like [`run`], with extra environment variables and `input` written to its stdin. A command
+/// still running after `timeout` is killed.
+pub fn spawn(
+    command: Vec<String>,
+    environment: Vec<(String, String)>,
+    input: Option<String>,
+    timeout: Option<std::time::Duration>,
+) -> iced::Task<Message> {
+    iced::Task::future(async move {
+        use tokio::io::AsyncWriteExt;
+
+        let Some((program, arguments)) = command.split_first() else {
+            return;
+        };
+        let child = tokio::process::Command::new(program)
+            .args(arguments)
+            .envs(environment)
+            .stdin(if input.is_some() {
+                std::process::Stdio::piped()
+            } else {
+                std::process::Stdio::null()
+            })
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!("Failed to run command \"{program}\": {e}");
+                return;
+            }
+        };
+
+        let stdin = child.stdin.take();
+        let finished = async {
+            if let (Some(mut stdin), Some(input)) = (stdin, input) {
+                // commands are free to ignore their input
+                if let Err(e) = stdin.write_all(input.as_bytes()).await {
+                    log::debug!("Command \"{program}\" did not take its input: {e}");
+                }
+            }
+            child.wait().await
+        };
+        let status = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, finished).await {
+                Ok(status) => status,
+                Err(_) => {
+                    log::warn!("Command \"{program}\" timed out after {timeout:?}, killing it");
+                    if let Err(e) = child.kill().await {
+                        log::warn!("Failed to kill command \"{program}\": {e}");
+                    }
+                    return;
+                }
+            },
+            None => finished.await,
+        };
+        match status {
+            Ok(status) if !status.success() => {
+                log::warn!("Command \"{program}\" exited with {status}");
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to wait for command \"{program}\": {e}"),
+        }
+    })
+    .discard()
+}
@@ -0,0 +1,62 @@
+use futures::SinkExt;
+use notify::Watcher;
+
+use crate::gui::app::Message;
+
+/// Watches the config file and sends a freshly parsed [`Config`] on every change.
+/// The parent directory is watched instead of the file itself, as most editors save by
+/// replacing the file, which would silently drop an inotify watch on the old inode.
+///
+/// [`Config`]: crate::data::config::Config
+pub async fn watch(
+    path: std::path::PathBuf,
+    mut sender: futures::channel::mpsc::Sender<Message>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(dir) = path.parent().filter(|dir| dir.exists()) else {
+        log::warn!("Config directory of {path:?} does not exist, hot-reload disabled");
+        return Ok(());
+    };
+
+    let (event_sender, mut event_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        event_sender.send(event).ok();
+    })?;
+    watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+    log::info!("Watching {path:?} for changes");
+
+    while let Some(event) = event_receiver.recv().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("Config watcher error: {e}");
+                continue;
+            }
+        };
+        if !(event.kind.is_modify() || event.kind.is_create())
+            || !event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == path.file_name())
+        {
+            continue;
+        }
+
+        // editors tend to produce a burst of events per save, only act on the last one
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        while event_receiver.try_recv().is_ok() {}
+
+        if !path.exists() {
+            continue;
+        }
+        match crate::data::config::Config::load(Some(&path)) {
+            Ok(config) => {
+                if let Err(e) = sender.send(Message::ConfigReload(Box::new(config))).await {
+                    log::error!("Failed to send message: {e}");
+                }
+            }
+            Err(e) => log::error!("Config change rejected, keeping the previous one: {e}"),
+        }
+    }
+
+    Ok(())
+}
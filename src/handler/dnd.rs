@@ -0,0 +1,152 @@
+/// What the `dnd` IPC command asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DndCommand {
+    On,
+    Off,
+    Toggle,
+    /// back to following `[[dnd.schedule]]`
+    Auto,
+    /// on for the given number of minutes, then back to what it was
+    Snooze(u32),
+    Status,
+}
+
+impl DndCommand {
+    pub fn parse(arguments: &[&str]) -> Result<Self, String> {
+        match arguments {
+            [] | ["status"] => Ok(Self::Status),
+            ["on"] => Ok(Self::On),
+            ["off"] => Ok(Self::Off),
+            ["toggle"] => Ok(Self::Toggle),
+            ["auto"] => Ok(Self::Auto),
+            ["snooze", minutes] => minutes
+                .parse()
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .map(Self::Snooze)
+                .ok_or_else(|| format!("invalid number of minutes \"{minutes}\"")),
+            _ => Err(format!(
+                "unknown dnd command \"{}\", expected on, off, toggle, auto, snooze <minutes> \
+                 or status",
+                arguments.join(" ")
+            )),
+        }
+    }
+}
+
+/// Do not disturb state. Popups are held back while it is active, either switched on over
+/// IPC, snoozed for a while or by the schedule.
+#[derive(Debug, Default)]
+pub struct DoNotDisturb {
+    /// set over IPC, `None` follows the schedule
+    manual: Option<bool>,
+    snoozed_until: Option<std::time::SystemTime>,
+}
+
+impl DoNotDisturb {
+    pub fn new(config: &crate::data::config::DndConfig) -> Self {
+        Self {
+            manual: config.enable.then_some(true),
+            snoozed_until: None,
+        }
+    }
+
+    pub fn apply(&mut self, command: DndCommand, config: &crate::data::config::DndConfig) {
+        match command {
+            DndCommand::On | DndCommand::Off => {
+                self.manual = Some(command == DndCommand::On);
+                self.snoozed_until = None;
+            }
+            DndCommand::Toggle => {
+                self.manual = Some(!self.active(config));
+                self.snoozed_until = None;
+            }
+            DndCommand::Auto => {
+                self.manual = None;
+                self.snoozed_until = None;
+            }
+            DndCommand::Snooze(minutes) => {
+                self.snoozed_until = std::time::SystemTime::now()
+                    .checked_add(std::time::Duration::from_secs(minutes as u64 * 60));
+            }
+            DndCommand::Status => {}
+        }
+    }
+
+    pub fn active(&self, config: &crate::data::config::DndConfig) -> bool {
+        self.snooze_left().is_some() || self.manual.unwrap_or_else(|| scheduled(config))
+    }
+
+    /// Whether the state may change without a command, so it has to be checked now and then
+    pub fn timed(&self, config: &crate::data::config::DndConfig) -> bool {
+        self.snooze_left().is_some() || (self.manual.is_none() && !config.schedule.is_empty())
+    }
+
+    /// "off", "on", "on scheduled" or "on snoozed 25m", the first word is enough for a bar
+    pub fn status(&self, config: &crate::data::config::DndConfig) -> String {
+        if let Some(left) = self.snooze_left() {
+            return format!("on snoozed {}m", left.as_secs().div_ceil(60));
+        }
+        match self.manual {
+            Some(true) => "on".to_string(),
+            Some(false) => "off".to_string(),
+            None if scheduled(config) => "on scheduled".to_string(),
+            None => "off".to_string(),
+        }
+    }
+
+    fn snooze_left(&self) -> Option<std::time::Duration> {
+        self.snoozed_until
+            .and_then(|until| until.duration_since(std::time::SystemTime::now()).ok())
+    }
+}
+
+fn scheduled(config: &crate::data::config::DndConfig) -> bool {
+    !config.schedule.is_empty() && config.scheduled(&jiff::Zoned::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(DndCommand::parse(&[]), Ok(DndCommand::Status));
+        assert_eq!(DndCommand::parse(&["status"]), Ok(DndCommand::Status));
+        assert_eq!(DndCommand::parse(&["on"]), Ok(DndCommand::On));
+        assert_eq!(DndCommand::parse(&["off"]), Ok(DndCommand::Off));
+        assert_eq!(DndCommand::parse(&["toggle"]), Ok(DndCommand::Toggle));
+        assert_eq!(DndCommand::parse(&["auto"]), Ok(DndCommand::Auto));
+        assert_eq!(
+            DndCommand::parse(&["snooze", "25"]),
+            Ok(DndCommand::Snooze(25))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_commands() {
+        assert!(DndCommand::parse(&["snooze"]).is_err());
+        assert!(DndCommand::parse(&["snooze", "0"]).is_err());
+        assert!(DndCommand::parse(&["snooze", "-5"]).is_err());
+        assert!(DndCommand::parse(&["snooze", "soon"]).is_err());
+        assert!(DndCommand::parse(&["on", "now"]).is_err());
+        assert!(DndCommand::parse(&["maybe"]).is_err());
+    }
+
+    #[test]
+    fn commands_switch_the_state() {
+        let config = crate::data::config::DndConfig::default();
+        let mut dnd = DoNotDisturb::new(&config);
+        assert_eq!(dnd.status(&config), "off");
+        dnd.apply(DndCommand::Toggle, &config);
+        assert_eq!(dnd.status(&config), "on");
+        dnd.apply(DndCommand::Snooze(25), &config);
+        assert_eq!(dnd.status(&config), "on snoozed 25m");
+        assert!(dnd.timed(&config));
+        dnd.apply(DndCommand::Off, &config);
+        assert!(!dnd.active(&config));
+        dnd.apply(DndCommand::Auto, &config);
+        assert_eq!(dnd.status(&config), "off");
+        assert!(!dnd.timed(&config));
+    }
+}
@@ -40,3 +40,32 @@ impl From<ErrorHandler> for zbus::fdo::Error {
         }
     }
 }
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: std::path::PathBuf,
+        error: std::io::Error,
+    },
+    Invalid {
+        path: std::path::PathBuf,
+        diagnostics: Vec<crate::data::validation::Diagnostic>,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => write!(f, "failed to read {path:?}: {error}"),
+            ConfigError::Invalid { path, diagnostics } => {
+                write!(f, "invalid config {path:?}")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}:{diagnostic}", path.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
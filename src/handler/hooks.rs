@@ -0,0 +1,222 @@
+use crate::gui::app::Message;
+
+/// What happened to a notification, each event has its own hook in `[hooks]`
+#[derive(Debug, Clone)]
+pub enum HookEvent {
+    Notify,
+    Close(crate::data::notification::CloseReason),
+    Action(String),
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Notify => "notify",
+            Self::Close(_) => "close",
+            Self::Action(_) => "action",
+        }
+    }
+}
+
+/// Runs the hook of `event` if one is configured, it never blocks the update loop
+pub fn run(
+    config: &crate::data::config::HooksConfig,
+    event: HookEvent,
+    notification: &crate::data::notification::Notification,
+) -> iced::Task<Message> {
+    let command = match event {
+        HookEvent::Notify => &config.on_notify,
+        HookEvent::Close(_) => &config.on_close,
+        HookEvent::Action(_) => &config.on_action,
+    };
+    if command.is_empty() {
+        return iced::Task::none();
+    }
+    let timeout = std::time::Duration::try_from_secs_f64(config.timeout)
+        .ok()
+        .filter(|timeout| !timeout.is_zero());
+    crate::handler::command::spawn(
+        command.clone(),
+        environment(&event, notification),
+        to_json(&event, notification),
+        timeout,
+    )
+}
+
+/// `IWWC_*` variables, missing hints are set empty so scripts can rely on them existing
+fn environment(
+    event: &HookEvent,
+    notification: &crate::data::notification::Notification,
+) -> Vec<(String, String)> {
+    let (reason, action) = match event {
+        HookEvent::Notify => ("", ""),
+        HookEvent::Close(reason) => (reason.name(), ""),
+        HookEvent::Action(action) => ("", action.as_str()),
+    };
+    [
+        ("IWWC_EVENT", event.name().to_string()),
+        ("IWWC_ID", notification.notification_id.to_string()),
+        ("IWWC_APP_NAME", notification.app_name.clone()),
+        ("IWWC_APP_ICON", notification.app_icon.clone()),
+        ("IWWC_SUMMARY", notification.summary.clone()),
+        (
+            "IWWC_BODY",
+            crate::data::markup::plain_text(&notification.body),
+        ),
+        (
+            "IWWC_URGENCY",
+            notification.hints.urgency.name().to_string(),
+        ),
+        (
+            "IWWC_CATEGORY",
+            notification.hints.category.clone().unwrap_or_default(),
+        ),
+        (
+            "IWWC_DESKTOP_ENTRY",
+            notification.hints.desktop_entry.clone().unwrap_or_default(),
+        ),
+        ("IWWC_CLOSE_REASON", reason.to_string()),
+        ("IWWC_ACTION", action.to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect()
+}
+
+/// What a hook reads from its stdin, `body` keeps its markup and `body_text` is without it
+#[derive(Debug, serde::Serialize)]
+struct Payload<'a> {
+    event: &'static str,
+    id: u32,
+    app_name: &'a str,
+    app_icon: &'a str,
+    summary: &'a str,
+    body: &'a str,
+    body_text: String,
+    actions: Vec<PayloadAction<'a>>,
+    urgency: &'static str,
+    category: Option<&'a str>,
+    desktop_entry: Option<&'a str>,
+    expire_timeout: i32,
+    resident: bool,
+    transient: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<&'a str>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct PayloadAction<'a> {
+    key: &'a str,
+    label: &'a str,
+}
+
+/// The payload as one JSON object on a single line
+fn to_json(
+    event: &HookEvent,
+    notification: &crate::data::notification::Notification,
+) -> Option<String> {
+    let payload = Payload {
+        event: event.name(),
+        id: notification.notification_id,
+        app_name: &notification.app_name,
+        app_icon: &notification.app_icon,
+        summary: &notification.summary,
+        body: &notification.body,
+        body_text: crate::data::markup::plain_text(&notification.body),
+        actions: notification
+            .actions
+            .chunks_exact(2)
+            .map(|pair| PayloadAction {
+                key: &pair[0],
+                label: &pair[1],
+            })
+            .collect(),
+        urgency: notification.hints.urgency.name(),
+        category: notification.hints.category.as_deref(),
+        desktop_entry: notification.hints.desktop_entry.as_deref(),
+        expire_timeout: notification.expire_timeout,
+        resident: notification.hints.resident,
+        transient: notification.hints.transient,
+        reason: match event {
+            HookEvent::Close(reason) => Some(reason.name()),
+            _ => None,
+        },
+        action: match event {
+            HookEvent::Action(action) => Some(action),
+            _ => None,
+        },
+    };
+    match serde_json::to_string(&payload) {
+        Ok(json) => Some(json + "\n"),
+        Err(e) => {
+            log::error!("Failed to serialize the hook payload: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification() -> crate::data::notification::Notification {
+        crate::data::notification::Notification {
+            app_name: "app \"quoted\"".to_string(),
+            app_icon: String::new(),
+            replaces_id: 0,
+            summary: "line\nbreak\ttab \\ \u{1} é".to_string(),
+            body: "<b>bold</b> &amp; more".to_string(),
+            actions: vec!["default".to_string(), "Open".to_string()],
+            expire_timeout: -1,
+            notification_id: 7,
+            hints: crate::data::notification::Hints {
+                category: Some("im.received".to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn payload_is_one_line_of_json() {
+        let json = to_json(&HookEvent::Notify, &notification()).unwrap();
+        assert_eq!(json.lines().count(), 1);
+        assert!(json.ends_with('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["event"], "notify");
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["app_name"], "app \"quoted\"");
+        assert_eq!(value["summary"], "line\nbreak\ttab \\ \u{1} é");
+        assert_eq!(value["body"], "<b>bold</b> &amp; more");
+        assert_eq!(value["body_text"], "bold & more");
+        assert_eq!(
+            value["actions"],
+            serde_json::json!([{"key": "default", "label": "Open"}])
+        );
+        assert_eq!(value["urgency"], "normal");
+        assert_eq!(value["category"], "im.received");
+        assert_eq!(value["desktop_entry"], serde_json::Value::Null);
+        assert_eq!(value["expire_timeout"], -1);
+        assert!(value.get("reason").is_none());
+        assert!(value.get("action").is_none());
+    }
+
+    #[test]
+    fn payload_carries_the_event_details() {
+        let close = to_json(
+            &HookEvent::Close(crate::data::notification::CloseReason::Dismissed),
+            &notification(),
+        )
+        .unwrap();
+        let close: serde_json::Value = serde_json::from_str(&close).unwrap();
+        assert_eq!(close["event"], "close");
+        assert_eq!(close["reason"], "dismissed");
+
+        let action = to_json(&HookEvent::Action("reply".to_string()), &notification()).unwrap();
+        let action: serde_json::Value = serde_json::from_str(&action).unwrap();
+        assert_eq!(action["event"], "action");
+        assert_eq!(action["action"], "reply");
+    }
+}
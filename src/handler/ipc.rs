@@ -2,11 +2,34 @@ use futures::SinkExt;
 use futures::channel::mpsc;
 use std::fs;
 use std::path::PathBuf;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 
 use crate::gui::app::Message;
 
+/// Answer to an IPC command, written back to the client before the connection is closed
+#[derive(Debug, Clone)]
+pub struct Reply(
+    std::sync::Arc<std::sync::Mutex<Option<futures::channel::oneshot::Sender<String>>>>,
+);
+
+impl Reply {
+    fn new() -> (Self, futures::channel::oneshot::Receiver<String>) {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        (
+            Self(std::sync::Arc::new(std::sync::Mutex::new(Some(sender)))),
+            receiver,
+        )
+    }
+
+    /// Only the first answer is sent, the message may have been cloned
+    pub fn send(&self, answer: String) {
+        if let Some(sender) = self.0.lock().ok().and_then(|mut sender| sender.take()) {
+            sender.send(answer).ok();
+        }
+    }
+}
+
 pub struct IpcServer {
     listener: UnixListener,
 }
@@ -41,19 +64,51 @@ impl IpcServer {
         match reader.read_line(&mut line).await {
             Ok(0) => return Ok(()), // Connection closed
             Ok(_) => {
-                let command = line.trim();
-                log::debug!("Received IPC command: {command}");
-
-                let message = match command {
-                    "test" => Message::TestMessage,
+                // arguments follow the command separated by tabs
+                let mut arguments = line.trim().split('\t');
+                let command = arguments.next().unwrap_or_default();
+                let arguments: Vec<&str> = arguments.collect();
+                log::debug!("Received IPC command: {command} {arguments:?}");
+
+                // commands with a reply get an answer channel, invalid arguments are answered
+                // right away
+                let parsed = match command {
+                    "test" => Ok((Message::TestMessage, None)),
+                    "toggle-center" => Ok((Message::ToggleCenter, None)),
+                    "history" => {
+                        crate::data::history::HistoryQuery::parse(&arguments).map(|query| {
+                            let (reply, answer) = Reply::new();
+                            (Message::HistoryQuery(query, reply), Some(answer))
+                        })
+                    }
+                    "dnd" => crate::handler::dnd::DndCommand::parse(&arguments).map(|dnd| {
+                        let (reply, answer) = Reply::new();
+                        (Message::Dnd(dnd, reply), Some(answer))
+                    }),
                     _ => {
                         log::warn!("Unknown IPC command: {command}");
                         return Ok(());
                     }
                 };
+                let (message, answer) = match parsed {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        reader
+                            .get_mut()
+                            .write_all(format!("error: {e}\n").as_bytes())
+                            .await?;
+                        return Ok(());
+                    }
+                };
 
                 if let Err(e) = sender.send(message).await {
                     log::error!("Failed to send message: {e}");
+                    return Ok(());
+                }
+                if let Some(answer) = answer {
+                    if let Ok(answer) = answer.await {
+                        reader.get_mut().write_all(answer.as_bytes()).await?;
+                    }
                 }
             }
             Err(e) => {
@@ -85,14 +140,28 @@ impl IpcServer {
         PathBuf::from(runtime_dir).join("iwwc.sock")
     }
 
-    pub async fn send_ipc_command(command: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Sends `command` with its arguments and prints whatever the daemon answers
+    pub async fn send_ipc_command(
+        command: &str,
+        arguments: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let socket_path = Self::get_socket_path();
-        use tokio::io::AsyncWriteExt;
+        use tokio::io::AsyncReadExt;
         match tokio::net::UnixStream::connect(&socket_path).await {
             Ok(mut stream) => {
-                let message = format!("{command}\n");
-                stream.write_all(message.as_bytes()).await?;
+                let message = std::iter::once(command)
+                    .chain(arguments.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\t");
+                stream.write_all(format!("{message}\n").as_bytes()).await?;
                 log::debug!("Command '{command}' sent successfully");
+
+                let mut answer = String::new();
+                stream.read_to_string(&mut answer).await?;
+                print!("{answer}");
+                if answer.starts_with("error: ") {
+                    std::process::exit(1);
+                }
             }
             Err(_) => {
                 log::error!("Failed to connect to daemon. Is the daemon running?");
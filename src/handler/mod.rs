@@ -1,3 +1,8 @@
+pub mod command;
+pub mod config;
+pub mod dnd;
 pub mod error;
+pub mod hooks;
 pub mod ipc;
 pub mod notification;
+pub mod timers;
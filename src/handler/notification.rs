@@ -17,7 +17,10 @@ impl NotificationHandler {
     #[allow(non_snake_case)]
     async fn CloseNotification(&mut self, notification_id: u32) -> zbus::fdo::Result<()> {
         self.sender
-            .try_send(Message::CloseByContentId(notification_id))
+            .try_send(Message::CloseByContentId(
+                notification_id,
+                crate::data::notification::CloseReason::CloseNotification,
+            ))
             .ok();
         Ok(())
     }
@@ -41,10 +44,19 @@ impl NotificationHandler {
             replaces_id
         };
 
-        let desktop_entry = if hints.contains_key("desktop-entry") {
-            hints["desktop-entry"].to_string()
+        let hints = parse_hints(hints);
+
+        // without a capability the client is expected to not rely on it, and what it sends
+        // anyway is treated the way a server without the feature would
+        let body = if crate::data::shared::has_capability("body-markup") {
+            body
+        } else {
+            crate::data::markup::escape(&body)
+        };
+        let actions = if crate::data::shared::has_capability("actions") {
+            actions
         } else {
-            String::new()
+            Vec::new()
         };
 
         let notification = crate::data::notification::Notification {
@@ -56,7 +68,7 @@ impl NotificationHandler {
             actions,
             expire_timeout,
             notification_id,
-            desktop_entry,
+            hints,
         };
 
         self.sender.try_send(Message::Notify(notification)).ok();
@@ -66,92 +78,511 @@ impl NotificationHandler {
 
     #[allow(non_snake_case)]
     fn GetServerInformation(&mut self) -> zbus::fdo::Result<(String, String, String, String)> {
-        let name = std::env::var("CARGO_PKG_DESCRIPTION")
-            .unwrap_or_else(|_| "No description found".to_string());
-        let vendor =
-            std::env::var("CARGO_PKG_NAME").unwrap_or_else(|_| "No name found".to_string());
-        let version =
-            std::env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "No version found".to_string());
+        let name = env!("CARGO_PKG_NAME").to_string();
+        let vendor = env!("CARGO_PKG_AUTHORS").replace(':', ", ");
+        let version = env!("CARGO_PKG_VERSION").to_string();
+        // 1.3 needs the ActivationToken signal, which is not emitted yet
         let spec_version = String::from("1.2");
 
         Ok((name, vendor, version, spec_version))
     }
 
     #[allow(non_snake_case)]
-    fn GetCapabilities(&mut self) -> zbus::fdo::Result<Vec<&str>> {
-        let capabilities = vec![
-            "action-icons",
-            "actions",
-            "body",
-            "body-hyperlinks",
-            "body-images",
-            "body-markup",
-            "icon-multi",
-            "icon-static",
-            "persistence",
-            "sound",
-        ];
-
-        Ok(capabilities)
+    fn GetCapabilities(&mut self) -> zbus::fdo::Result<Vec<String>> {
+        crate::data::shared::CAPABILITIES
+            .read()
+            .map(|capabilities| capabilities.clone())
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Capabilities unavailable: {e}")))
     }
+
+    #[allow(non_snake_case)]
+    #[zbus(signal)]
+    async fn NotificationClosed(
+        emitter: &zbus::object_server::SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[allow(non_snake_case)]
+    #[zbus(signal)]
+    async fn ActionInvoked(
+        emitter: &zbus::object_server::SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
 }
 
-pub fn handle_notification(
-    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
-    notification: crate::data::notification::Notification,
-) -> iced::Task<Message> {
-    let mut overflow = iced::Task::none();
-    let id = notification.notification_id;
+/// Emits the D-Bus signal matching an action reported by the gui
+pub async fn emit_action(
+    connection: &zbus::Connection,
+    action: crate::data::notification::NotificationAction,
+) -> Result<(), crate::handler::error::ErrorHandler> {
+    use crate::data::notification::NotificationAction;
+
+    let emitter =
+        zbus::object_server::SignalEmitter::new(connection, "/org/freedesktop/Notifications")?;
+    match action {
+        NotificationAction::ActionClose {
+            notification_id,
+            reason,
+        } => {
+            log::debug!("Notification {notification_id} closed, reason: {reason:?}");
+            NotificationHandler::NotificationClosed(&emitter, notification_id, reason as u32)
+                .await?;
+        }
+        NotificationAction::ActionInvoked {
+            notification_id,
+            action_key,
+        } => {
+            log::debug!("Notification {notification_id} action invoked: {action_key}");
+            NotificationHandler::ActionInvoked(&emitter, notification_id, &action_key).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Converts the raw hints dictionary into [`Hints`]. Clients are not too strict about the
+/// types they send (e.g. urgency as an int instead of a byte), so numbers and booleans are
+/// accepted in any integer form and anything else is ignored with a debug log.
+///
+/// [`Hints`]: crate::data::notification::Hints
+pub fn parse_hints(
+    mut hints: std::collections::HashMap<String, zbus::zvariant::Value<'_>>,
+) -> crate::data::notification::Hints {
+    use crate::data::notification::{Hints, Urgency};
+    use zbus::zvariant::Value;
 
-    if iwwc.notification_ids.len() >= iwwc.config.notifications.max_notifications as usize {
-        if let Some((_, info)) = iwwc.notification_ids.shift_remove_index(0) {
-            overflow =
-                iced::Task::done(Message::CloseByContentId(info.notification.notification_id));
+    fn unwrap_variant(value: Value<'_>) -> Value<'_> {
+        match value {
+            Value::Value(inner) => unwrap_variant(*inner),
+            value => value,
         }
     }
 
-    let timeout = if iwwc.config.notifications.respect_notification_timeout
-        && notification.expire_timeout > 0
+    fn as_i64(value: &Value<'_>) -> Option<i64> {
+        match value {
+            Value::U8(v) => Some(*v as i64),
+            Value::I16(v) => Some(*v as i64),
+            Value::U16(v) => Some(*v as i64),
+            Value::I32(v) => Some(*v as i64),
+            Value::U32(v) => Some(*v as i64),
+            Value::I64(v) => Some(*v),
+            Value::U64(v) => i64::try_from(*v).ok(),
+            Value::Str(v) => v.as_str().trim().parse().ok(),
+            Value::Value(v) => as_i64(v),
+            _ => None,
+        }
+    }
+
+    fn as_bool(value: &Value<'_>) -> Option<bool> {
+        match value {
+            Value::Bool(v) => Some(*v),
+            Value::Str(v) => match v.as_str().trim() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            Value::Value(v) => as_bool(v),
+            value => as_i64(value).map(|v| v != 0),
+        }
+    }
+
+    fn as_string(value: &Value<'_>) -> Option<String> {
+        match value {
+            Value::Str(v) => Some(v.as_str().to_string()),
+            Value::ObjectPath(v) => Some(v.as_str().to_string()),
+            Value::Value(v) => as_string(v),
+            _ => None,
+        }
+    }
+
+    fn next_i32<'a>(fields: &mut impl Iterator<Item = Value<'a>>) -> Option<i32> {
+        match fields.next()? {
+            Value::I32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn as_image(value: Value<'_>) -> Option<crate::data::notification::ImageData> {
+        let Value::Structure(structure) = unwrap_variant(value) else {
+            return None;
+        };
+        let mut fields = structure.into_fields().into_iter().map(unwrap_variant);
+        let width = next_i32(&mut fields)?;
+        let height = next_i32(&mut fields)?;
+        let rowstride = next_i32(&mut fields)?;
+        let Value::Bool(has_alpha) = fields.next()? else {
+            return None;
+        };
+        let bits_per_sample = next_i32(&mut fields)?;
+        let channels = next_i32(&mut fields)?;
+        let Value::Array(data) = fields.next()? else {
+            return None;
+        };
+        let data = Vec::<u8>::try_from(data).ok()?;
+        Some(crate::data::notification::ImageData {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            bits_per_sample,
+            channels,
+            data,
+        })
+    }
+
+    let mut parsed = Hints::default();
+    let invalid = |key: &str| log::debug!("Ignoring hint \"{key}\" with unexpected type");
+
+    if let Some(value) = hints.get("urgency") {
+        match as_i64(value) {
+            Some(0) => parsed.urgency = Urgency::Low,
+            Some(1) => parsed.urgency = Urgency::Normal,
+            Some(2) => parsed.urgency = Urgency::Critical,
+            _ => invalid("urgency"),
+        }
+    }
+
+    for (key, target) in [
+        ("category", &mut parsed.category),
+        ("desktop-entry", &mut parsed.desktop_entry),
+        ("sound-file", &mut parsed.sound_file),
+        ("sound-name", &mut parsed.sound_name),
+    ] {
+        if let Some(value) = hints.get(key) {
+            *target = as_string(value).filter(|v| !v.is_empty());
+            if target.is_none() {
+                invalid(key);
+            }
+        }
+    }
+    parsed.image_path = ["image-path", "image_path"]
+        .into_iter()
+        .find_map(|key| hints.get(key).and_then(as_string))
+        .filter(|v| !v.is_empty());
+
+    for (key, target) in [
+        ("suppress-sound", &mut parsed.suppress_sound),
+        ("transient", &mut parsed.transient),
+        ("resident", &mut parsed.resident),
+        ("action-icons", &mut parsed.action_icons),
+    ] {
+        if let Some(value) = hints.get(key) {
+            match as_bool(value) {
+                Some(v) => *target = v,
+                None => invalid(key),
+            }
+        }
+    }
+
+    for (key, target) in [("x", &mut parsed.x), ("y", &mut parsed.y)] {
+        if let Some(value) = hints.get(key) {
+            *target = as_i64(value).and_then(|v| i32::try_from(v).ok());
+            if target.is_none() {
+                invalid(key);
+            }
+        }
+    }
+    if let Some(value) = hints.get("value") {
+        parsed.value = as_i64(value).map(|v| v.clamp(0, 100) as i32);
+        if parsed.value.is_none() {
+            invalid("value");
+        }
+    }
+
+    // the older spellings are only checked when the current one is missing or broken
+    for key in ["image-data", "image_data", "icon_data"] {
+        if let Some(value) = hints.remove(key) {
+            parsed.image_data = as_image(value);
+            if parsed.image_data.is_some() {
+                break;
+            }
+            invalid(key);
+        }
+    }
+
+    parsed
+}
+
+/// The icon a rule sets, a name or a path
+fn rule_icon(
+    icon: &str,
+    size: u32,
+) -> Option<crate::gui::elements::notification::NotificationIcon> {
+    let found = crate::data::icons::path_from_icon_value(icon)
+        .or_else(|| crate::data::shared::ICONS.find_icon(icon, size))
+        .and_then(crate::gui::elements::notification::NotificationIcon::from_path);
+    if found.is_none() {
+        log::warn!("Icon \"{icon}\" set by a rule not found, using the notification's own");
+    }
+    found
+}
+
+/// Picks the icon in the order the spec asks for: `image-data`, `image-path`, `app_icon`,
+/// then falls back to guessing from the app name and finally the default icon. Without
+/// `respect_icon` the icon sent along is skipped and only the guesses are tried.
+fn resolve_icon(
+    notification: &crate::data::notification::Notification,
+    respect_icon: bool,
+    size: u32,
+) -> crate::gui::elements::notification::NotificationIcon {
+    use crate::gui::elements::notification::NotificationIcon;
+
+    if let Some(image_data) = notification
+        .hints
+        .image_data
+        .as_ref()
+        .filter(|_| respect_icon)
     {
-        notification.expire_timeout
+        match image_data.to_rgba() {
+            Some((width, height, pixels)) => {
+                return NotificationIcon::Raster(iced::widget::image::Handle::from_rgba(
+                    width, height, pixels,
+                ));
+            }
+            None => log::warn!(
+                "Unsupported image-data from {}: {}x{}, {} channels, {} bits per sample",
+                notification.app_name,
+                image_data.width,
+                image_data.height,
+                image_data.channels,
+                image_data.bits_per_sample
+            ),
+        }
+    }
+
+    let app_name = notification.app_name.to_lowercase();
+    let desktop_entries = &crate::data::shared::DESKTOP_ENTRIES;
+    let (image_path, app_icon) = if respect_icon {
+        (
+            notification.hints.image_path.as_deref().unwrap_or_default(),
+            notification.app_icon.as_str(),
+        )
     } else {
-        iwwc.config.notifications.local_expire_timeout
+        ("", "")
     };
+    let icon = [
+        image_path,
+        app_icon,
+        notification
+            .hints
+            .desktop_entry
+            .as_deref()
+            .and_then(|entry| desktop_entries.icon_for_id(entry))
+            .unwrap_or_default(),
+        desktop_entries
+            .icon_for_app_name(&notification.app_name)
+            .unwrap_or_default(),
+        app_name.as_str(),
+    ]
+    .into_iter()
+    .filter(|value| !value.is_empty())
+    .find_map(|value| {
+        let path = crate::data::icons::path_from_icon_value(value)
+            .or_else(|| crate::data::shared::ICONS.find_icon(value, size))?;
+        NotificationIcon::from_path(path)
+    });
 
-    let icons = crate::data::shared::ICONS.lock().unwrap();
+    icon.unwrap_or_else(|| {
+        NotificationIcon::Svg(std::path::PathBuf::from(
+            std::env::var("HOME").unwrap_or_default() + "/.config/iwwc/default.svg",
+        ))
+    })
+}
 
-    let icon_name = if !notification.app_icon.is_empty() {
-        notification.app_icon.clone()
-    } else if !notification.app_name.is_empty() {
-        notification.app_name.clone().to_lowercase()
-    } else {
-        "default".to_string()
-    };
+/// Resolves the icon on the blocking pool, lookups hit the filesystem and must not stall
+/// the update loop. The `rules` are evaluated and the body markup is parsed on the way, an
+/// icon set by a rule is looked up instead of the notification's own. The raw `image-data`
+/// is dropped once the icon is resolved.
+pub async fn load_notification(
+    mut notification: crate::data::notification::Notification,
+    rules: Vec<crate::data::config::Rule>,
+    respect_icon: bool,
+    icon_size: u32,
+) -> Option<crate::gui::elements::notification::NotificationWindowInfo> {
+    let id = notification.notification_id;
+    let info = tokio::task::spawn_blocking(move || {
+        let rules = crate::data::rules::evaluate(&rules, &notification);
+        let icon = rules
+            .icon
+            .as_deref()
+            .and_then(|icon| rule_icon(icon, icon_size))
+            .unwrap_or_else(|| resolve_icon(&notification, respect_icon, icon_size));
+        // the icon holds its own copy of the pixels, no need to clone them on every view
+        notification.hints.image_data = None;
+        let mut body = crate::data::markup::parse(&notification.body);
+        if !crate::data::shared::has_capability("body-hyperlinks") {
+            body.iter_mut().for_each(|span| span.link = None);
+        }
+        crate::gui::elements::notification::NotificationWindowInfo {
+            notification,
+            icon,
+            body,
+            expanded: false,
+            rules,
+        }
+    })
+    .await;
+    match info {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::error!("Failed to resolve the icon of notification {id}: {e}");
+            None
+        }
+    }
+}
 
-    let icon = if let Some(icon) = icons.get(&icon_name) {
-        icon.clone()
-    } else {
-        std::path::PathBuf::from(std::env::var("HOME").unwrap() + "/.config/iwwc/default.svg")
-    };
+/// Applies the `[[rules]]`, records the notification and shows it, replaces the shown one or
+/// holds it back during do not disturb
+pub fn handle_notification(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    mut info: crate::gui::elements::notification::NotificationWindowInfo,
+) -> iced::Task<Message> {
+    let id = info.notification.notification_id;
+    let replaced = iwwc
+        .notification_ids
+        .iter()
+        .find(|(_, shown)| shown.notification.notification_id == id)
+        .map(|(window_id, _)| *window_id)
+        .filter(|_| info.notification.replaces_id != 0);
 
-    let window_id = iced::window::Id::unique();
+    // dropped notifications are closed right away, neither rule commands nor hooks see them
+    if info.rules.drop {
+        log::debug!(
+            "Notification {id} from {} dropped by a rule",
+            info.notification.app_name
+        );
+        return close_unshown(iwwc, &info.notification, replaced, false);
+    }
 
-    iwwc.notification_ids.insert(
-        window_id,
-        crate::gui::elements::notification::NotificationWindowInfo { notification, icon },
+    if let Some(urgency) = info.rules.urgency {
+        info.notification.hints.urgency = urgency;
+    }
+    let commands = iced::Task::batch(
+        info.rules
+            .exec
+            .iter()
+            .cloned()
+            .map(crate::handler::command::run)
+            .chain([crate::handler::hooks::run(
+                &iwwc.config.hooks,
+                crate::handler::hooks::HookEvent::Notify,
+                &info.notification,
+            )]),
     );
 
+    iwwc.history.record(&info.notification, info.icon.path());
+    let save = iwwc.save_history();
+    // muted notifications only go to the history
+    if info.rules.mute {
+        return iced::Task::batch([
+            close_unshown(iwwc, &info.notification, replaced, true),
+            save,
+            commands,
+        ]);
+    }
+    // a replacement for a notification that is already gone is shown as a new one
+    if let Some(window_id) = replaced {
+        return iced::Task::batch([replace_notification(iwwc, window_id, info), save, commands]);
+    }
+
+    // held back notifications stay open without expiring, they are shown once do not
+    // disturb ends
+    if iwwc.dnd.active(&iwwc.config.dnd) && !iwwc.config.dnd.allows(&info.notification) {
+        iwwc.held_notifications.insert(id, info);
+        return iced::Task::batch([save, commands]);
+    }
+    iwwc.held_notifications.shift_remove(&id);
+
+    iced::Task::batch([show_notification(iwwc, info), save, commands])
+}
+
+/// Closes a notification a rule keeps off screen, along with the window of the one it replaces.
+/// `close_hook` runs the close hook for it, dropped notifications never reached the hooks.
+fn close_unshown(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    notification: &crate::data::notification::Notification,
+    replaced: Option<iced::window::Id>,
+    close_hook: bool,
+) -> iced::Task<Message> {
+    let id = notification.notification_id;
+    let reason = crate::data::notification::CloseReason::Undefined;
+    if let Some(window_id) = replaced {
+        return iwwc.close_notification(window_id, reason);
+    }
+    iwwc.held_notifications.shift_remove(&id);
+    iwwc.history.close(id, reason);
+    iwwc.send_action(crate::data::notification::NotificationAction::ActionClose {
+        notification_id: id,
+        reason,
+    });
+    if !close_hook {
+        return iwwc.save_history();
+    }
+    iced::Task::batch([
+        iwwc.save_history(),
+        crate::handler::hooks::run(
+            &iwwc.config.hooks,
+            crate::handler::hooks::HookEvent::Close(reason),
+            notification,
+        ),
+    ])
+}
+
+/// Opens the window of a notification that is not shown yet
+pub fn show_notification(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    info: crate::gui::elements::notification::NotificationWindowInfo,
+) -> iced::Task<Message> {
+    use crate::data::notification::Urgency;
+
+    let id = info.notification.notification_id;
+    let mut overflow = iced::Task::none();
+    let critical = info.notification.hints.urgency == Urgency::Critical;
+
+    let max_notifications = iwwc.config.notifications.max_notifications;
+    if max_notifications > 0 && iwwc.notification_ids.len() >= max_notifications as usize {
+        // critical notifications are never evicted, only the oldest of the others
+        let evicted = iwwc
+            .notification_ids
+            .iter()
+            .find(|(_, info)| info.notification.hints.urgency != Urgency::Critical)
+            .map(|(window_id, _)| *window_id);
+        if let Some(window_id) = evicted {
+            overflow = iwwc
+                .close_notification(window_id, crate::data::notification::CloseReason::Undefined);
+        }
+    }
+
+    let timeout = expire_timeout(&iwwc.config, &info);
+
+    let window_id = iced::window::Id::unique();
+
+    let height = iwwc.notification_height(&info);
+    let location = info.location(&iwwc.config);
+    // critical notifications go on top of the stack, below the ones already there
+    let position = if critical {
+        iwwc.notification_ids
+            .values()
+            .position(|info| info.notification.hints.urgency != Urgency::Critical)
+            .unwrap_or(iwwc.notification_ids.len())
+    } else {
+        iwwc.notification_ids.len()
+    };
+    iwwc.notification_ids
+        .shift_insert(position, window_id, info);
+
+    let expire = iwwc.timers.restart(id, timeout);
+
     iced::Task::batch([
         overflow,
         iced::Task::done(Message::MoveNotifications),
         iced::Task::done(Message::NewLayerShell {
             settings: iced_layershell::reexport::NewLayerShellSettings {
-                size: Some((
-                    iwwc.config.notifications.width,
-                    iwwc.config.notifications.height,
-                )),
+                size: Some((iwwc.config.notifications.width, height)),
                 exclusive_zone: None,
-                anchor: iwwc.config.notifications.location,
+                anchor: location,
                 layer: iced_layershell::reexport::Layer::Overlay,
                 margin: Some((
                     iwwc.config.notifications.vertical_margin,
@@ -165,9 +596,186 @@ pub fn handle_notification(
             },
             id: window_id,
         }),
-        iced::Task::perform(
-            tokio::time::sleep(std::time::Duration::from_secs(timeout.try_into().unwrap())),
-            move |_| Message::CloseByContentId(id),
-        ),
+        expire,
+    ])
+}
+
+/// Updates a shown notification in place, it keeps its window and stack position and the
+/// expiry timer starts over
+fn replace_notification(
+    iwwc: &mut crate::gui::app::IcedWaylandWidgetCenter,
+    window_id: iced::window::Id,
+    mut info: crate::gui::elements::notification::NotificationWindowInfo,
+) -> iced::Task<Message> {
+    let id = info.notification.notification_id;
+    let timeout = expire_timeout(&iwwc.config, &info);
+    if let Some(shown) = iwwc.notification_ids.get(&window_id) {
+        info.expanded = shown.expanded;
+    }
+    let height = iwwc.notification_height(&info);
+    let location = info.location(&iwwc.config);
+    if let Some(shown) = iwwc.notification_ids.get_mut(&window_id) {
+        *shown = info;
+    }
+    let expire = iwwc.timers.restart(id, timeout);
+    // the new timer must not run while the pointer is still over the notification
+    if iwwc.config.notifications.pause_on_hover && iwwc.hovered == Some(window_id) {
+        iwwc.timers.pause(id);
+    }
+
+    iced::Task::batch([
+        // actions may have been added or removed, which changes the height
+        iced::Task::done(Message::AnchorSizeChange {
+            id: window_id,
+            anchor: location,
+            size: (iwwc.config.notifications.width, height),
+        }),
+        iced::Task::done(Message::MoveNotifications),
+        expire,
     ])
 }
+
+/// A rule's `expire_timeout` wins over whatever the sender or `[notifications]` ask for
+fn expire_timeout(
+    config: &crate::data::config::Config,
+    info: &crate::gui::elements::notification::NotificationWindowInfo,
+) -> Option<std::time::Duration> {
+    match info.rules.expire_timeout {
+        Some(seconds) => std::time::Duration::try_from_secs_f64(seconds)
+            .ok()
+            .filter(|timeout| !timeout.is_zero()),
+        None => config.notifications.expire_timeout(&info.notification),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::notification::{ImageData, Urgency};
+    use zbus::zvariant::Value;
+
+    fn hints<'a>(
+        entries: impl IntoIterator<Item = (&'a str, Value<'a>)>,
+    ) -> std::collections::HashMap<String, Value<'a>> {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    fn image(width: i32) -> Value<'static> {
+        Value::from((
+            width,
+            1,
+            width * 3,
+            false,
+            8,
+            3,
+            vec![0u8; width as usize * 3],
+        ))
+    }
+
+    #[test]
+    fn wrong_types_are_ignored() {
+        let parsed = parse_hints(hints([
+            ("urgency", Value::from("very")),
+            ("category", Value::from(3u32)),
+            ("desktop-entry", Value::from(true)),
+            ("transient", Value::from(1.5)),
+            ("x", Value::from("left")),
+            ("value", Value::from(vec![1u8])),
+            ("image-data", Value::from("not an image")),
+            ("image-path", Value::from(7i32)),
+        ]));
+        assert_eq!(parsed, crate::data::notification::Hints::default());
+    }
+
+    #[test]
+    fn urgency_is_read_from_any_integer() {
+        for (value, urgency) in [
+            (Value::from(0u8), Urgency::Low),
+            (Value::from(2u8), Urgency::Critical),
+            (Value::from(2i32), Urgency::Critical),
+            (Value::new(Value::from(0u32)), Urgency::Low),
+            (Value::from(3u8), Urgency::Normal),
+        ] {
+            assert_eq!(parse_hints(hints([("urgency", value)])).urgency, urgency);
+        }
+    }
+
+    #[test]
+    fn desktop_entry_is_kept_unless_empty() {
+        let parsed = parse_hints(hints([(
+            "desktop-entry",
+            Value::from("org.gnome.Nautilus"),
+        )]));
+        assert_eq!(parsed.desktop_entry.as_deref(), Some("org.gnome.Nautilus"));
+        let parsed = parse_hints(hints([("desktop-entry", Value::from(""))]));
+        assert_eq!(parsed.desktop_entry, None);
+    }
+
+    #[test]
+    fn image_data_wins_over_the_deprecated_keys() {
+        let parsed = parse_hints(hints([
+            ("icon_data", image(3)),
+            ("image_data", image(2)),
+            ("image-data", image(1)),
+        ]));
+        assert_eq!(parsed.image_data.map(|image| image.width), Some(1));
+
+        let parsed = parse_hints(hints([("icon_data", image(3)), ("image_data", image(2))]));
+        assert_eq!(parsed.image_data.map(|image| image.width), Some(2));
+
+        let parsed = parse_hints(hints([
+            ("image-data", Value::from("broken")),
+            ("icon_data", image(3)),
+        ]));
+        assert_eq!(
+            parsed.image_data,
+            Some(ImageData {
+                width: 3,
+                height: 1,
+                rowstride: 9,
+                has_alpha: false,
+                bits_per_sample: 8,
+                channels: 3,
+                data: vec![0; 9],
+            })
+        );
+    }
+
+    #[test]
+    fn image_path_accepts_uris_and_icon_names() {
+        let file = std::env::temp_dir().join(format!("iwwc-image-path-{}", std::process::id()));
+        std::fs::write(&file, b"").unwrap();
+        let uri = format!("file://{}", file.display());
+
+        let parsed = parse_hints(hints([("image-path", Value::from(uri.as_str()))]));
+        let image_path = parsed.image_path.unwrap();
+        assert_eq!(
+            crate::data::icons::path_from_icon_value(&image_path),
+            Some(file.clone())
+        );
+
+        let parsed = parse_hints(hints([("image_path", Value::from("dialog-information"))]));
+        let image_path = parsed.image_path.unwrap();
+        assert_eq!(image_path, "dialog-information");
+        assert_eq!(crate::data::icons::path_from_icon_value(&image_path), None);
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn transient_and_resident_are_booleans() {
+        let parsed = parse_hints(hints([
+            ("transient", Value::from(true)),
+            ("resident", Value::from(1u8)),
+        ]));
+        assert!(parsed.transient && parsed.resident);
+        let parsed = parse_hints(hints([
+            ("transient", Value::from("false")),
+            ("resident", Value::from(0i32)),
+        ]));
+        assert!(!parsed.transient && !parsed.resident);
+    }
+}
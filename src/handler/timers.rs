@@ -0,0 +1,203 @@
+use crate::gui::app::Message;
+
+/// Expiry timers of the shown notifications, keyed by notification id. Every start bumps
+/// the generation, a timer that fires after it was cancelled or replaced (ids are reused
+/// through `replaces_id`) is recognised as stale and ignored.
+#[derive(Debug, Default)]
+pub struct ExpiryTimers {
+    timers: std::collections::HashMap<u32, Timer>,
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Timer {
+    generation: u64,
+    state: TimerState,
+}
+
+#[derive(Debug)]
+enum TimerState {
+    Running {
+        /// aborts the sleeping task when dropped
+        _handle: iced::task::Handle,
+        deadline: std::time::Instant,
+    },
+    Paused {
+        remaining: std::time::Duration,
+    },
+}
+
+impl ExpiryTimers {
+    /// Starts the timer of `id` over with `timeout`, the one it had never fires. `None`
+    /// leaves it without a timer.
+    pub fn restart(
+        &mut self,
+        id: u32,
+        timeout: Option<std::time::Duration>,
+    ) -> iced::Task<Message> {
+        match timeout {
+            Some(timeout) => self.run(id, timeout),
+            None => {
+                self.cancel(id);
+                iced::Task::none()
+            }
+        }
+    }
+
+    pub fn cancel(&mut self, id: u32) {
+        self.timers.remove(&id);
+    }
+
+    /// Stops the timer of `id` and keeps the time it had left
+    pub fn pause(&mut self, id: u32) {
+        if let Some(Timer {
+            state: TimerState::Running { deadline, .. },
+            ..
+        }) = self.timers.get(&id)
+        {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            self.pause_with(id, remaining);
+        }
+    }
+
+    /// Continues a paused timer with what it had left, but at least `minimum`
+    pub fn resume(&mut self, id: u32, minimum: std::time::Duration) -> iced::Task<Message> {
+        match self.timers.get(&id) {
+            Some(Timer {
+                state: TimerState::Paused { remaining },
+                ..
+            }) => {
+                let remaining = (*remaining).max(minimum);
+                self.run(id, remaining)
+            }
+            _ => iced::Task::none(),
+        }
+    }
+
+    /// Whether a fired timer is the current one of `id`, the timer is then forgotten
+    pub fn expire(&mut self, id: u32, generation: u64) -> bool {
+        let current = self.timers.get(&id).is_some_and(|timer| {
+            timer.generation == generation && matches!(timer.state, TimerState::Running { .. })
+        });
+        if current {
+            self.timers.remove(&id);
+        }
+        current
+    }
+
+    fn run(&mut self, id: u32, remaining: std::time::Duration) -> iced::Task<Message> {
+        // too far in the future to be represented, that is as good as never
+        let Some(deadline) = std::time::Instant::now().checked_add(remaining) else {
+            self.cancel(id);
+            return iced::Task::none();
+        };
+        self.generation += 1;
+        let generation = self.generation;
+        let (task, handle) = iced::Task::perform(tokio::time::sleep(remaining), move |_| {
+            Message::Expire(id, generation)
+        })
+        .abortable();
+        self.timers.insert(
+            id,
+            Timer {
+                generation,
+                state: TimerState::Running {
+                    _handle: handle.abort_on_drop(),
+                    deadline,
+                },
+            },
+        );
+        task
+    }
+
+    fn pause_with(&mut self, id: u32, remaining: std::time::Duration) {
+        if let Some(timer) = self.timers.get_mut(&id) {
+            self.generation += 1;
+            timer.generation = self.generation;
+            timer.state = TimerState::Paused { remaining };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+    /// Time left of a running or paused timer
+    fn remaining(timers: &ExpiryTimers, id: u32) -> std::time::Duration {
+        match &timers.timers[&id].state {
+            TimerState::Running { deadline, .. } => {
+                deadline.saturating_duration_since(std::time::Instant::now())
+            }
+            TimerState::Paused { remaining } => *remaining,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_current_generation_expires() {
+        let mut timers = ExpiryTimers::default();
+        let _ = timers.restart(1, Some(TIMEOUT));
+        let stale = timers.timers[&1].generation;
+        let _ = timers.restart(1, Some(TIMEOUT));
+        let current = timers.timers[&1].generation;
+
+        assert!(!timers.expire(1, stale));
+        assert!(!timers.expire(2, current));
+        assert!(timers.expire(1, current));
+        // forgotten once it expired
+        assert!(!timers.expire(1, current));
+    }
+
+    #[tokio::test]
+    async fn cancelled_timers_never_expire() {
+        let mut timers = ExpiryTimers::default();
+        let _ = timers.restart(1, Some(TIMEOUT));
+        let generation = timers.timers[&1].generation;
+        timers.cancel(1);
+        assert!(!timers.expire(1, generation));
+
+        let _ = timers.restart(1, Some(TIMEOUT));
+        let generation = timers.timers[&1].generation;
+        let _ = timers.restart(1, None);
+        assert!(!timers.expire(1, generation));
+    }
+
+    #[tokio::test]
+    async fn paused_timers_keep_their_time() {
+        let mut timers = ExpiryTimers::default();
+        let _ = timers.restart(1, Some(TIMEOUT));
+        let running = timers.timers[&1].generation;
+        timers.pause(1);
+        let paused = timers.timers[&1].generation;
+        let left = remaining(&timers, 1);
+        assert!(left <= TIMEOUT && left > TIMEOUT - std::time::Duration::from_secs(1));
+        // neither the timer from before the pause nor a paused one expires
+        assert!(!timers.expire(1, running));
+        assert!(!timers.expire(1, paused));
+
+        let _ = timers.resume(1, std::time::Duration::from_secs(2));
+        assert!(remaining(&timers, 1) <= left);
+        assert!(remaining(&timers, 1) > left - std::time::Duration::from_secs(1));
+        assert!(!timers.expire(1, paused));
+        let resumed = timers.timers[&1].generation;
+        assert!(timers.expire(1, resumed));
+    }
+
+    #[tokio::test]
+    async fn resume_gives_at_least_the_minimum() {
+        let mut timers = ExpiryTimers::default();
+        let _ = timers.restart(1, Some(std::time::Duration::from_millis(100)));
+        timers.pause(1);
+        let _ = timers.resume(1, TIMEOUT);
+        assert!(remaining(&timers, 1) > TIMEOUT - std::time::Duration::from_secs(1));
+
+        // resuming a timer that is not paused changes nothing
+        let generation = timers.timers[&1].generation;
+        let _ = timers.resume(1, TIMEOUT);
+        assert_eq!(timers.timers[&1].generation, generation);
+        let _ = timers.resume(2, TIMEOUT);
+        assert!(!timers.timers.contains_key(&2));
+    }
+}
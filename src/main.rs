@@ -17,6 +17,10 @@ struct Args {
 
     #[arg(value_name = "COMMAND")]
     command: Option<String>,
+
+    /// Command arguments, e.g. the file for check-config or `app=firefox` filters for history
+    #[arg(value_name = "ARGS")]
+    arguments: Vec<String>,
 }
 
 #[tokio::main]
@@ -34,14 +38,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Some(command) = args.command {
         match command.as_str() {
-            "test" => crate::handler::ipc::IpcServer::send_ipc_command("test").await?,
+            "test" | "history" | "toggle-center" | "dnd" => {
+                crate::handler::ipc::IpcServer::send_ipc_command(&command, &args.arguments).await?
+            }
+            "check-config" => {
+                let path = args
+                    .arguments
+                    .first()
+                    .map(std::path::PathBuf::from)
+                    .or(args.config)
+                    .unwrap_or_else(crate::data::config::Config::default_path);
+                if !check_config(&path) {
+                    std::process::exit(1);
+                }
+            }
             "daemon" => {
                 if crate::handler::ipc::IpcServer::is_active().await {
                     log::error!("Daemon is already running.");
                     std::process::exit(1);
                 }
-                crate::data::icons::get_system_icons_paths();
-                crate::gui::app::start().expect("REASON");
+                let config = match crate::data::config::Config::load(args.config.as_deref()) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        log::error!("Failed to load config, using defaults: {e}");
+                        crate::data::config::Config::default()
+                    }
+                };
+                crate::data::icons::default_icon();
+                let config_path = args
+                    .config
+                    .unwrap_or_else(crate::data::config::Config::default_path);
+                crate::gui::app::start(config, config_path).expect("REASON");
             }
             _ => {
                 log::error!("Unknown command: {command}");
@@ -55,3 +82,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+fn check_config(path: &std::path::Path) -> bool {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("{}: failed to read: {e}", path.display());
+            return false;
+        }
+    };
+
+    let (config, diagnostics) = crate::data::validation::check(&content);
+    for diagnostic in &diagnostics {
+        eprintln!("{}:{diagnostic}", path.display());
+        if let Some(line) = content.lines().nth(diagnostic.line.saturating_sub(1)) {
+            eprintln!("    {line}");
+            eprintln!("    {}^", " ".repeat(diagnostic.column.saturating_sub(1)));
+        }
+    }
+
+    if config.is_some() {
+        println!("{}: config is valid", path.display());
+        true
+    } else {
+        false
+    }
+}